@@ -0,0 +1,164 @@
+//! This module contains a `ComputeBackend` abstraction over the heavy
+//! kernels of the prover: multiexps in G1 and G2 and FFTs over the
+//! scalar field. An external accelerator (GPU, FPGA, ...) can implement
+//! it and be plugged into the prover without forking it.
+//!
+//! Any error reported by a backend is not fatal: the kernel is recomputed
+//! on the CPU and a warning is logged.
+
+use crate::pairing::{
+    Engine
+};
+
+use crate::pairing::ff::{
+    PrimeField
+};
+
+use super::SynthesisError;
+use super::worker::Worker;
+use super::domain::{best_fft, Scalar};
+use super::multiexp::dense_multiexp;
+
+/// Implementation of the heavy prover kernels.
+pub trait ComputeBackend<E: Engine>: Send + Sync {
+    /// Human readable name of the backend that is used in the warnings.
+    fn name(&self) -> &str;
+
+    /// Whether the kernels are the ones of this crate on the CPU. The prover
+    /// then runs its own multiexps over the sparse queries, streaming the
+    /// bases from the `ParameterSource`, and does not need to guard against
+    /// failed kernels.
+    fn is_cpu(&self) -> bool {
+        false
+    }
+
+    /// Computes sum of `bases[i] * exponents[i]` in G1. Both slices
+    /// have the same length.
+    fn multiexp_g1(
+        &self,
+        worker: &Worker,
+        bases: &[E::G1Affine],
+        exponents: &[<E::Fr as PrimeField>::Repr]
+    ) -> Result<E::G1, SynthesisError>;
+
+    /// Computes sum of `bases[i] * exponents[i]` in G2. Both slices
+    /// have the same length.
+    fn multiexp_g2(
+        &self,
+        worker: &Worker,
+        bases: &[E::G2Affine],
+        exponents: &[<E::Fr as PrimeField>::Repr]
+    ) -> Result<E::G2, SynthesisError>;
+
+    /// Performs in-place FFT of size `2^log_n` with the root of unity `omega`.
+    /// Content of `a` is not used if the error is returned.
+    fn fft_fr(
+        &self,
+        worker: &Worker,
+        a: &mut [Scalar<E>],
+        omega: &E::Fr,
+        log_n: u32
+    ) -> Result<(), SynthesisError>;
+}
+
+/// Backend that runs the kernels of this crate on the CPU.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuBackend;
+
+impl<E: Engine> ComputeBackend<E> for CpuBackend {
+    fn name(&self) -> &str {
+        "CPU"
+    }
+
+    fn is_cpu(&self) -> bool {
+        true
+    }
+
+    fn multiexp_g1(
+        &self,
+        worker: &Worker,
+        bases: &[E::G1Affine],
+        exponents: &[<E::Fr as PrimeField>::Repr]
+    ) -> Result<E::G1, SynthesisError>
+    {
+        dense_multiexp(worker, bases, exponents)
+    }
+
+    fn multiexp_g2(
+        &self,
+        worker: &Worker,
+        bases: &[E::G2Affine],
+        exponents: &[<E::Fr as PrimeField>::Repr]
+    ) -> Result<E::G2, SynthesisError>
+    {
+        dense_multiexp(worker, bases, exponents)
+    }
+
+    fn fft_fr(
+        &self,
+        worker: &Worker,
+        a: &mut [Scalar<E>],
+        omega: &E::Fr,
+        log_n: u32
+    ) -> Result<(), SynthesisError>
+    {
+        best_fft(a, worker, omega, log_n)
+    }
+}
+
+pub(crate) fn multiexp_g1_with_fallback<E: Engine, B: ComputeBackend<E> + ?Sized>(
+    backend: &B,
+    worker: &Worker,
+    bases: &[E::G1Affine],
+    exponents: &[<E::Fr as PrimeField>::Repr]
+) -> Result<E::G1, SynthesisError>
+{
+    match backend.multiexp_g1(worker, bases, exponents) {
+        Ok(result) => Ok(result),
+        Err(e) => {
+            elog!("WARNING: multiexp in G1 has failed on {} backend ({}), falling back to CPU", backend.name(), e);
+            dense_multiexp(worker, bases, exponents)
+        }
+    }
+}
+
+pub(crate) fn multiexp_g2_with_fallback<E: Engine, B: ComputeBackend<E> + ?Sized>(
+    backend: &B,
+    worker: &Worker,
+    bases: &[E::G2Affine],
+    exponents: &[<E::Fr as PrimeField>::Repr]
+) -> Result<E::G2, SynthesisError>
+{
+    match backend.multiexp_g2(worker, bases, exponents) {
+        Ok(result) => Ok(result),
+        Err(e) => {
+            elog!("WARNING: multiexp in G2 has failed on {} backend ({}), falling back to CPU", backend.name(), e);
+            dense_multiexp(worker, bases, exponents)
+        }
+    }
+}
+
+pub(crate) fn fft_with_fallback<E: Engine, B: ComputeBackend<E> + ?Sized>(
+    backend: &B,
+    worker: &Worker,
+    a: &mut [Scalar<E>],
+    omega: &E::Fr,
+    log_n: u32
+) -> Result<(), SynthesisError>
+{
+    if backend.is_cpu() {
+        return backend.fft_fr(worker, a, omega, log_n);
+    }
+
+    // backend is free to trash the input on failure, so keep a copy
+    let original = a.to_vec();
+
+    match backend.fft_fr(worker, a, omega, log_n) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            elog!("WARNING: FFT has failed on {} backend ({}), falling back to CPU", backend.name(), e);
+            a.copy_from_slice(&original);
+            best_fft(a, worker, omega, log_n)
+        }
+    }
+}
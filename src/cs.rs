@@ -1,12 +1,14 @@
 use crate::pairing::{Engine};
 use crate::pairing::ff::Field;
 
-use std::ops::{Add, Sub};
+use std::ops::{Add, Sub, Mul, Neg};
 use std::fmt;
 use std::error::Error;
 use std::io;
 use std::marker::PhantomData;
 
+use crate::worker::WorkerError;
+
 extern crate tokio;
 
 use tokio::runtime::Runtime;
@@ -24,7 +26,8 @@ pub trait Circuit<E: Engine> {
 }
 
 /// Represents a variable in our constraint system.
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+/// Variables are ordered by their `Index`.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Variable(pub(crate) Index);
 
 impl Variable {
@@ -39,16 +42,53 @@ impl Variable {
     pub fn get_unchecked(&self) -> Index {
         self.0
     }
+
+    /// This returns the index underlying the variable.
+    pub fn index(&self) -> Index {
+        self.0
+    }
+}
+
+impl fmt::Display for Variable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.0.fmt(f)
+    }
 }
 
 /// Represents the index of either an input variable or
-/// auxillary variable.
-#[derive(Copy, Clone, PartialEq, Debug, Hash, Eq)]
+/// auxillary variable. All inputs are ordered before the auxillary
+/// variables, and variables of the same kind by their number.
+#[derive(Copy, Clone, PartialEq, Debug, Hash, Eq, PartialOrd, Ord)]
 pub enum Index {
     Input(usize),
     Aux(usize)
 }
 
+impl Index {
+    pub fn as_input(&self) -> Option<usize> {
+        match *self {
+            Index::Input(i) => Some(i),
+            Index::Aux(_) => None
+        }
+    }
+
+    pub fn as_aux(&self) -> Option<usize> {
+        match *self {
+            Index::Input(_) => None,
+            Index::Aux(i) => Some(i)
+        }
+    }
+}
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Index::Input(i) => write!(f, "in({})", i),
+            Index::Aux(i) => write!(f, "aux({})", i)
+        }
+    }
+}
+
 /// This represents a linear combination of some variables, with coefficients
 /// in the scalar field of a pairing-friendly elliptic curve group.
 #[derive(Clone, Debug, Eq)]
@@ -70,6 +110,37 @@ impl<E: Engine> LinearCombination<E> {
     pub fn zero() -> LinearCombination<E> {
         LinearCombination(vec![])
     }
+
+    /// Number of terms, including duplicated variables.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, (Variable, E::Fr)> {
+        self.0.iter()
+    }
+
+    /// Sorts the terms by variable, inputs first, merges the terms of
+    /// the same variable and drops the terms with zero coefficients.
+    /// The value of the linear combination does not change.
+    pub fn normalize(&mut self) {
+        self.0.sort_by_key(|(var, _)| *var);
+
+        let mut terms: Vec<(Variable, E::Fr)> = Vec::with_capacity(self.0.len());
+        for (var, coeff) in self.0.drain(..) {
+            match terms.last_mut() {
+                Some(last) if last.0 == var => last.1.add_assign(&coeff),
+                _ => terms.push((var, coeff))
+            }
+        }
+        terms.retain(|(_, coeff)| !coeff.is_zero());
+
+        self.0 = terms;
+    }
 }
 
 impl<E: Engine> Add<(E::Fr, Variable)> for LinearCombination<E> {
@@ -160,6 +231,48 @@ impl<'a, E: Engine> Sub<(E::Fr, &'a LinearCombination<E>)> for LinearCombination
     }
 }
 
+impl<E: Engine> Add<LinearCombination<E>> for LinearCombination<E> {
+    type Output = LinearCombination<E>;
+
+    fn add(mut self, other: LinearCombination<E>) -> LinearCombination<E> {
+        self.0.extend(other.0);
+
+        self
+    }
+}
+
+impl<E: Engine> Sub<LinearCombination<E>> for LinearCombination<E> {
+    type Output = LinearCombination<E>;
+
+    fn sub(self, other: LinearCombination<E>) -> LinearCombination<E> {
+        self + (-other)
+    }
+}
+
+impl<E: Engine> Mul<E::Fr> for LinearCombination<E> {
+    type Output = LinearCombination<E>;
+
+    fn mul(mut self, scalar: E::Fr) -> LinearCombination<E> {
+        for (_, coeff) in self.0.iter_mut() {
+            coeff.mul_assign(&scalar);
+        }
+
+        self
+    }
+}
+
+impl<E: Engine> Neg for LinearCombination<E> {
+    type Output = LinearCombination<E>;
+
+    fn neg(mut self) -> LinearCombination<E> {
+        for (_, coeff) in self.0.iter_mut() {
+            coeff.negate();
+        }
+
+        self
+    }
+}
+
 /// This is an error that could occur during circuit synthesis contexts,
 /// such as CRS generation, proving or verification.
 #[derive(Debug)]
@@ -178,8 +291,38 @@ pub enum SynthesisError {
     IoError(io::Error),
     /// During verification, our verifying key was malformed.
     MalformedVerifyingKey,
+    /// During verification, the number of public inputs did not match the verifying key.
+    InputLengthMismatch { expected: usize, got: usize },
+    /// During verification, a public input was not smaller than the modulus.
+    NonCanonicalInput { index: usize },
     /// During CRS generation, we observed an unconstrained auxillary variable
-    UnconstrainedVariable
+    UnconstrainedVariable,
+    /// A computation dispatched on the worker pool has panicked
+    WorkerPanicked(WorkerError),
+    /// During witness synthesis, the circuit did not match its cached shape
+    ShapeMismatch,
+    /// Two inputs of a computation that go together, like the exponents of
+    /// a multiexp and their density map, have different lengths.
+    LengthMismatch { expected: usize, got: usize },
+    /// The error `source` occurred at the variable with the namespaced `path`,
+    /// see `TrackedCircuit`.
+    AtPath { path: String, source: Box<SynthesisError> }
+}
+
+impl SynthesisError {
+    /// Returns the error without the paths where it occurred.
+    pub fn kind(&self) -> &SynthesisError {
+        match *self {
+            SynthesisError::AtPath { ref source, .. } => source.kind(),
+            _ => self
+        }
+    }
+}
+
+impl From<WorkerError> for SynthesisError {
+    fn from(e: WorkerError) -> SynthesisError {
+        SynthesisError::WorkerPanicked(e)
+    }
 }
 
 impl From<io::Error> for SynthesisError {
@@ -198,18 +341,47 @@ impl Error for SynthesisError {
             SynthesisError::UnexpectedIdentity => "encountered an identity element in the CRS",
             SynthesisError::IoError(_) => "encountered an I/O error",
             SynthesisError::MalformedVerifyingKey => "malformed verifying key",
-            SynthesisError::UnconstrainedVariable => "auxillary variable was unconstrained"
+            SynthesisError::InputLengthMismatch { .. } => "number of public inputs does not match the verifying key",
+            SynthesisError::NonCanonicalInput { .. } => "public input is not smaller than the modulus",
+            SynthesisError::UnconstrainedVariable => "auxillary variable was unconstrained",
+            SynthesisError::WorkerPanicked(_) => "computation on the worker pool has panicked",
+            SynthesisError::ShapeMismatch => "circuit does not match its cached shape",
+            SynthesisError::LengthMismatch { .. } => "inputs of the computation have different lengths",
+            SynthesisError::AtPath { .. } => "synthesis failed at a variable of the circuit"
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            SynthesisError::IoError(ref e) => Some(e),
+            SynthesisError::AtPath { ref source, .. } => Some(&**source),
+            _ => None
         }
     }
 }
 
 impl fmt::Display for SynthesisError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        if let &SynthesisError::IoError(ref e) = self {
-            write!(f, "I/O error: ")?;
-            e.fmt(f)
-        } else {
-            write!(f, "{}", self.description())
+        match *self {
+            SynthesisError::IoError(ref e) => {
+                write!(f, "I/O error: ")?;
+                e.fmt(f)
+            },
+            SynthesisError::WorkerPanicked(ref e) => e.fmt(f),
+            SynthesisError::AtPath { ref path, ref source } => {
+                write!(f, "{}: ", path)?;
+                source.fmt(f)
+            },
+            SynthesisError::InputLengthMismatch { expected, got } => {
+                write!(f, "expected {} public inputs, got {}", expected, got)
+            },
+            SynthesisError::LengthMismatch { expected, got } => {
+                write!(f, "expected {} elements, got {}", expected, got)
+            },
+            SynthesisError::NonCanonicalInput { index } => {
+                write!(f, "public input {} is not smaller than the modulus", index)
+            },
+            _ => write!(f, "{}", self.description())
         }
     }
 }
@@ -224,11 +396,21 @@ pub trait ConstraintSystem<E: Engine>: Sized {
     /// so that nested namespaces can minimize indirection.
     type Root: ConstraintSystem<E>;
 
+    /// Whether the annotations of variables, constraints and namespaces
+    /// are used. Constraint systems of the provers and the generators
+    /// ignore them, so gadgets may skip formatting names, see `maybe_name!`.
+    const WANTS_ANNOTATIONS: bool = true;
+
     /// Return the "one" input variable
     fn one() -> Variable {
         Variable::new_unchecked(Index::Input(0))
     }
 
+    /// Same as `Self::WANTS_ANNOTATIONS`.
+    fn wants_annotations(&self) -> bool {
+        Self::WANTS_ANNOTATIONS
+    }
+
     /// Allocate a private variable in the constraint system. The provided function is used to
     /// determine the assignment of the variable. The given `annotation` function is invoked
     /// in testing contexts in order to derive a unique name for this variable in the current
@@ -263,6 +445,47 @@ pub trait ConstraintSystem<E: Engine>: Sized {
               LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
               LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>;
 
+    /// Enforce that `A` * `B` = `C` without naming the constraint.
+    /// Constraint systems that need unique names generate one.
+    fn enforce_unnamed<LA, LB, LC>(
+        &mut self,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        self.enforce(|| "", a, b, c)
+    }
+
+    /// Allocate a private variable `c` with the value `value` and enforce
+    /// that `a` * `b` = `c`. The constraint is named after the variable.
+    /// Constraint systems that have a native multiplication may override
+    /// this, so gadgets should prefer it to a separate `alloc` and `enforce`.
+    fn mul_allocate<F, A, AR>(
+        &mut self,
+        annotation: A,
+        a: &LinearCombination<E>,
+        b: &LinearCombination<E>,
+        value: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let name: String = if self.wants_annotations() { annotation().into() } else { String::new() };
+
+        let c = self.alloc(|| name.clone(), value)?;
+        self.enforce(
+            || format!("{}_constraint", name),
+            |lc| lc + a,
+            |lc| lc + b,
+            |lc| lc + c
+        );
+
+        Ok(c)
+    }
+
     /// Create a new (sub)namespace and enter into it. Not intended
     /// for downstream use; use `namespace` instead.
     fn push_namespace<NR, N>(&mut self, name_fn: N)
@@ -436,6 +659,8 @@ pub struct Namespace<'a, E: Engine, CS: ConstraintSystem<E> + 'a>(&'a mut CS, Ph
 impl<'cs, E: Engine, CS: ConstraintSystem<E>> ConstraintSystem<E> for Namespace<'cs, E, CS> {
     type Root = CS::Root;
 
+    const WANTS_ANNOTATIONS: bool = CS::WANTS_ANNOTATIONS;
+
     fn one() -> Variable {
         CS::one()
     }
@@ -475,6 +700,31 @@ impl<'cs, E: Engine, CS: ConstraintSystem<E>> ConstraintSystem<E> for Namespace<
         self.0.enforce(annotation, a, b, c)
     }
 
+    fn enforce_unnamed<LA, LB, LC>(
+        &mut self,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        self.0.enforce_unnamed(a, b, c)
+    }
+
+    fn mul_allocate<F, A, AR>(
+        &mut self,
+        annotation: A,
+        a: &LinearCombination<E>,
+        b: &LinearCombination<E>,
+        value: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        self.0.mul_allocate(annotation, a, b, value)
+    }
+
     // Downstream users who use `namespace` will never interact with these
     // functions and they will never be invoked because the namespace is
     // never a root constraint system.
@@ -524,6 +774,8 @@ impl<'a, E: Engine, CS: ConstraintSystem<E>> Drop for Namespace<'a, E, CS> {
 impl<'cs, E: Engine, CS: ConstraintSystem<E>> ConstraintSystem<E> for &'cs mut CS {
     type Root = CS::Root;
 
+    const WANTS_ANNOTATIONS: bool = CS::WANTS_ANNOTATIONS;
+
     fn one() -> Variable {
         CS::one()
     }
@@ -563,6 +815,31 @@ impl<'cs, E: Engine, CS: ConstraintSystem<E>> ConstraintSystem<E> for &'cs mut C
         (**self).enforce(annotation, a, b, c)
     }
 
+    fn enforce_unnamed<LA, LB, LC>(
+        &mut self,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        (**self).enforce_unnamed(a, b, c)
+    }
+
+    fn mul_allocate<F, A, AR>(
+        &mut self,
+        annotation: A,
+        a: &LinearCombination<E>,
+        b: &LinearCombination<E>,
+        value: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        (**self).mul_allocate(annotation, a, b, value)
+    }
+
     fn push_namespace<NR, N>(&mut self, name_fn: N)
         where NR: Into<String>, N: FnOnce() -> NR
     {
@@ -595,4 +872,276 @@ impl<'cs, E: Engine, CS: ConstraintSystem<E>> ConstraintSystem<E> for &'cs mut C
     {
         (**self).alloc_thread_output(annotation, f)
     }
-}
\ No newline at end of file
+}
+
+
+/// Formats an annotation only if the constraint system `cs` uses it,
+/// and returns an empty string otherwise, e.g.
+/// `let name = maybe_name!(cs, "bit {}", i);`
+#[macro_export]
+macro_rules! maybe_name {
+    ($cs:expr, $($arg:tt)*) => {
+        if $crate::ConstraintSystem::wants_annotations(&$cs) {
+            format!($($arg)*)
+        } else {
+            String::new()
+        }
+    };
+}
+
+/// Circuit that synthesizes `C` through a `TrackingConstraintSystem`, so
+/// errors of allocations are returned as `SynthesisError::AtPath`. This
+/// has to compute the annotations of all variables, so it is slower than
+/// synthesizing `C` directly.
+pub struct TrackedCircuit<C>(pub C);
+
+impl<E: Engine, C: Circuit<E>> Circuit<E> for TrackedCircuit<C> {
+    fn synthesize<CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS
+    ) -> Result<(), SynthesisError>
+    {
+        self.0.synthesize(&mut TrackingConstraintSystem::new(cs))
+    }
+}
+
+/// Constraint system that keeps the current namespace path and adds it
+/// to the errors of the allocations in the wrapped constraint system.
+pub struct TrackingConstraintSystem<E: Engine, CS: ConstraintSystem<E>> {
+    inner: CS,
+    namespace: Vec<String>,
+    _marker: PhantomData<E>
+}
+
+impl<E: Engine, CS: ConstraintSystem<E>> TrackingConstraintSystem<E, CS> {
+    pub fn new(inner: CS) -> Self {
+        TrackingConstraintSystem {
+            inner,
+            namespace: vec![],
+            _marker: PhantomData
+        }
+    }
+
+    pub fn into_inner(self) -> CS {
+        self.inner
+    }
+
+    fn at_path(&self, name: &str, e: SynthesisError) -> SynthesisError {
+        if let SynthesisError::AtPath { .. } = e {
+            return e;
+        }
+
+        let mut path = self.namespace.join("/");
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(name);
+
+        SynthesisError::AtPath { path, source: Box::new(e) }
+    }
+}
+
+impl<E: Engine, CS: ConstraintSystem<E>> ConstraintSystem<E> for TrackingConstraintSystem<E, CS> {
+    type Root = Self;
+
+    fn one() -> Variable {
+        CS::one()
+    }
+
+    fn alloc<F, A, AR>(
+        &mut self,
+        annotation: A,
+        f: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let name = annotation().into();
+        let result = self.inner.alloc(|| name.clone(), f);
+
+        result.map_err(|e| self.at_path(&name, e))
+    }
+
+    fn alloc_input<F, A, AR>(
+        &mut self,
+        annotation: A,
+        f: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let name = annotation().into();
+        let result = self.inner.alloc_input(|| name.clone(), f);
+
+        result.map_err(|e| self.at_path(&name, e))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(
+        &mut self,
+        annotation: A,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where A: FnOnce() -> AR, AR: Into<String>,
+              LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        self.inner.enforce(annotation, a, b, c)
+    }
+
+    fn enforce_unnamed<LA, LB, LC>(
+        &mut self,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        self.inner.enforce_unnamed(a, b, c)
+    }
+
+    fn mul_allocate<F, A, AR>(
+        &mut self,
+        annotation: A,
+        a: &LinearCombination<E>,
+        b: &LinearCombination<E>,
+        value: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let name = annotation().into();
+        let result = self.inner.mul_allocate(|| name.clone(), a, b, value);
+
+        result.map_err(|e| self.at_path(&name, e))
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+        where NR: Into<String>, N: FnOnce() -> NR
+    {
+        let name = name_fn().into();
+        self.inner.get_root().push_namespace(|| name.clone());
+        self.namespace.push(name);
+    }
+
+    fn pop_namespace(&mut self)
+    {
+        self.inner.get_root().pop_namespace();
+        assert!(self.namespace.pop().is_some());
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root
+    {
+        self
+    }
+
+    fn reserve_memory_for_thread_info(
+        &mut self
+    ) -> Sender<RememberedInfo<E>>
+    {
+        self.inner.reserve_memory_for_thread_info()
+    }
+
+    fn alloc_thread_output<F, A, AR>(
+        &mut self,
+        annotation: A,
+        f: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let name = annotation().into();
+        let result = self.inner.alloc_thread_output(|| name.clone(), f);
+
+        result.map_err(|e| self.at_path(&name, e))
+    }
+}
+
+#[test]
+fn test_linear_combination_normalization() {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::PrimeField;
+
+    let fr = |v: &str| Fr::from_str(v).unwrap();
+    let x = Variable(Index::Aux(3));
+    let y = Variable(Index::Aux(1));
+    let z = Variable(Index::Input(2));
+
+    let assignment = |var: Variable| match var.0 {
+        Index::Input(2) => fr("5"),
+        Index::Aux(1) => fr("7"),
+        Index::Aux(3) => fr("11"),
+        _ => unreachable!()
+    };
+    let eval = |lc: &LinearCombination<Bls12>| {
+        let mut acc = Fr::zero();
+        for (var, coeff) in lc.iter() {
+            let mut tmp = assignment(*var);
+            tmp.mul_assign(coeff);
+            acc.add_assign(&tmp);
+        }
+        acc
+    };
+
+    // 2x + y - z + 3x - y = 5x - z
+    let lc = LinearCombination::<Bls12>::zero() + (fr("2"), x) + y - z + (fr("3"), x) - y;
+    let mut normalized = lc.clone();
+    normalized.normalize();
+
+    assert_eq!(lc.len(), 5);
+    let mut minus_one = Fr::one();
+    minus_one.negate();
+    assert_eq!(normalized.as_ref(), &[(z, minus_one), (x, fr("5"))][..]);
+    assert_eq!(eval(&lc), eval(&normalized));
+
+    // normalizing twice changes nothing
+    let mut again = normalized.clone();
+    again.normalize();
+    assert!(again == normalized);
+
+    let difference = lc.clone() - normalized.clone();
+    assert_eq!(difference.len(), 7);
+    assert_eq!(eval(&difference), Fr::zero());
+    let mut difference = difference;
+    difference.normalize();
+    assert!(difference.is_empty());
+
+    let scaled = lc.clone() * fr("3");
+    let mut expected = eval(&lc);
+    expected.mul_assign(&fr("3"));
+    assert_eq!(eval(&scaled), expected);
+
+    let mut negated = eval(&lc);
+    negated.negate();
+    assert_eq!(eval(&(-lc.clone())), negated);
+    assert_eq!(eval(&(lc.clone() + (-lc))), Fr::zero());
+}
+
+#[test]
+fn test_variable_ordering() {
+    let mut vars = vec![
+        Variable(Index::Aux(17)),
+        Variable(Index::Input(3)),
+        Variable(Index::Aux(0)),
+        Variable(Index::Input(0)),
+        Variable(Index::Aux(2))
+    ];
+    vars.sort();
+
+    assert_eq!(vars, vec![
+        Variable(Index::Input(0)),
+        Variable(Index::Input(3)),
+        Variable(Index::Aux(0)),
+        Variable(Index::Aux(2)),
+        Variable(Index::Aux(17))
+    ]);
+
+    assert!(Variable(Index::Input(usize::MAX)) < Variable(Index::Aux(0)));
+    assert_eq!(vars[1].index().as_input(), Some(3));
+    assert_eq!(vars[1].index().as_aux(), None);
+    assert_eq!(vars[4].index().as_aux(), Some(17));
+    assert_eq!(vars[4].index().as_input(), None);
+
+    assert_eq!(format!("{}", vars[1]), "in(3)");
+    assert_eq!(format!("{}", vars[4]), "aux(17)");
+}
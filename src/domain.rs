@@ -25,6 +25,7 @@ use super::{
 };
 
 use super::worker::Worker;
+use super::backend::{ComputeBackend, fft_with_fallback};
 pub use super::group::*;
 
 pub struct EvaluationDomain<E: Engine, G: Group<E>> {
@@ -151,55 +152,51 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
     }
 
 
-    pub fn fft(&mut self, worker: &Worker)
+    pub fn fft(&mut self, worker: &Worker) -> Result<(), SynthesisError>
     {
-        best_fft(&mut self.coeffs, worker, &self.omega, self.exp);
+        best_fft(&mut self.coeffs, worker, &self.omega, self.exp)
     }
 
-    pub fn ifft(&mut self, worker: &Worker)
+    pub fn ifft(&mut self, worker: &Worker) -> Result<(), SynthesisError>
     {
-        best_fft(&mut self.coeffs, worker, &self.omegainv, self.exp);
+        best_fft(&mut self.coeffs, worker, &self.omegainv, self.exp)?;
 
-        worker.scope(self.coeffs.len(), |scope, chunk| {
-            let minv = self.minv;
+        let minv = self.minv;
 
-            for v in self.coeffs.chunks_mut(chunk) {
-                scope.spawn(move |_| {
-                    for v in v {
-                        v.group_mul_assign(&minv);
-                    }
-                });
+        worker.map_in_place(&mut self.coeffs, |_, v| {
+            for v in v {
+                v.group_mul_assign(&minv);
             }
-        });
+        })?;
+
+        Ok(())
     }
 
-    pub fn distribute_powers(&mut self, worker: &Worker, g: E::Fr)
+    pub fn distribute_powers(&mut self, worker: &Worker, g: E::Fr) -> Result<(), SynthesisError>
     {
-        worker.scope(self.coeffs.len(), |scope, chunk| {
-            for (i, v) in self.coeffs.chunks_mut(chunk).enumerate() {
-                scope.spawn(move |_| {
-                    let mut u = g.pow(&[(i * chunk) as u64]);
-                    for v in v.iter_mut() {
-                        v.group_mul_assign(&u);
-                        u.mul_assign(&g);
-                    }
-                });
+        worker.map_in_place(&mut self.coeffs, |start, v| {
+            let mut u = g.pow(&[start as u64]);
+            for v in v.iter_mut() {
+                v.group_mul_assign(&u);
+                u.mul_assign(&g);
             }
-        });
+        })?;
+
+        Ok(())
     }
 
-    pub fn coset_fft(&mut self, worker: &Worker)
+    pub fn coset_fft(&mut self, worker: &Worker) -> Result<(), SynthesisError>
     {
-        self.distribute_powers(worker, E::Fr::multiplicative_generator());
-        self.fft(worker);
+        self.distribute_powers(worker, E::Fr::multiplicative_generator())?;
+        self.fft(worker)
     }
 
-    pub fn icoset_fft(&mut self, worker: &Worker)
+    pub fn icoset_fft(&mut self, worker: &Worker) -> Result<(), SynthesisError>
     {
         let geninv = self.geninv;
 
-        self.ifft(worker);
-        self.distribute_powers(worker, geninv);
+        self.ifft(worker)?;
+        self.distribute_powers(worker, geninv)
     }
 
     /// This evaluates t(tau) for this domain, which is
@@ -214,60 +211,92 @@ impl<E: Engine, G: Group<E>> EvaluationDomain<E, G> {
     /// The target polynomial is the zero polynomial in our
     /// evaluation domain, so we must perform division over
     /// a coset.
-    pub fn divide_by_z_on_coset(&mut self, worker: &Worker)
+    pub fn divide_by_z_on_coset(&mut self, worker: &Worker) -> Result<(), SynthesisError>
     {
         let i = self.z(&E::Fr::multiplicative_generator()).inverse().unwrap();
 
-        worker.scope(self.coeffs.len(), |scope, chunk| {
-            for v in self.coeffs.chunks_mut(chunk) {
-                scope.spawn(move |_| {
-                    for v in v {
-                        v.group_mul_assign(&i);
-                    }
-                });
+        worker.map_in_place(&mut self.coeffs, |_, v| {
+            for v in v {
+                v.group_mul_assign(&i);
             }
-        });
+        })?;
+
+        Ok(())
     }
 
     /// Perform O(n) multiplication of two polynomials in the domain.
-    pub fn mul_assign(&mut self, worker: &Worker, other: &EvaluationDomain<E, Scalar<E>>) {
+    pub fn mul_assign(&mut self, worker: &Worker, other: &EvaluationDomain<E, Scalar<E>>) -> Result<(), SynthesisError> {
         assert_eq!(self.coeffs.len(), other.coeffs.len());
 
-        worker.scope(self.coeffs.len(), |scope, chunk| {
-            for (a, b) in self.coeffs.chunks_mut(chunk).zip(other.coeffs.chunks(chunk)) {
-                scope.spawn(move |_| {
-                    for (a, b) in a.iter_mut().zip(b.iter()) {
-                        a.group_mul_assign(&b.0);
-                    }
-                });
+        worker.map_in_place(&mut self.coeffs, |start, a| {
+            for (a, b) in a.iter_mut().zip(other.coeffs[start..].iter()) {
+                a.group_mul_assign(&b.0);
             }
-        });
+        })?;
+
+        Ok(())
     }
 
     /// Perform O(n) subtraction of one polynomial from another in the domain.
-    pub fn sub_assign(&mut self, worker: &Worker, other: &EvaluationDomain<E, G>) {
+    pub fn sub_assign(&mut self, worker: &Worker, other: &EvaluationDomain<E, G>) -> Result<(), SynthesisError> {
         assert_eq!(self.coeffs.len(), other.coeffs.len());
 
-        worker.scope(self.coeffs.len(), |scope, chunk| {
-            for (a, b) in self.coeffs.chunks_mut(chunk).zip(other.coeffs.chunks(chunk)) {
-                scope.spawn(move |_| {
-                    for (a, b) in a.iter_mut().zip(b.iter()) {
-                        a.group_sub_assign(&b);
-                    }
-                });
+        worker.map_in_place(&mut self.coeffs, |start, a| {
+            for (a, b) in a.iter_mut().zip(other.coeffs[start..].iter()) {
+                a.group_sub_assign(b);
             }
-        });
+        })?;
+
+        Ok(())
     }
 }
 
-pub(crate) fn best_fft<E: Engine, T: Group<E>>(a: &mut [T], worker: &Worker, omega: &E::Fr, log_n: u32)
+impl<E: Engine> EvaluationDomain<E, Scalar<E>> {
+    pub fn fft_with_backend<B: ComputeBackend<E> + ?Sized>(&mut self, worker: &Worker, backend: &B) -> Result<(), SynthesisError>
+    {
+        fft_with_fallback(backend, worker, &mut self.coeffs, &self.omega, self.exp)
+    }
+
+    pub fn ifft_with_backend<B: ComputeBackend<E> + ?Sized>(&mut self, worker: &Worker, backend: &B) -> Result<(), SynthesisError>
+    {
+        fft_with_fallback(backend, worker, &mut self.coeffs, &self.omegainv, self.exp)?;
+
+        let minv = self.minv;
+
+        worker.map_in_place(&mut self.coeffs, |_, v| {
+            for v in v {
+                v.group_mul_assign(&minv);
+            }
+        })?;
+
+        Ok(())
+    }
+
+    pub fn coset_fft_with_backend<B: ComputeBackend<E> + ?Sized>(&mut self, worker: &Worker, backend: &B) -> Result<(), SynthesisError>
+    {
+        self.distribute_powers(worker, E::Fr::multiplicative_generator())?;
+        self.fft_with_backend(worker, backend)
+    }
+
+    pub fn icoset_fft_with_backend<B: ComputeBackend<E> + ?Sized>(&mut self, worker: &Worker, backend: &B) -> Result<(), SynthesisError>
+    {
+        let geninv = self.geninv;
+
+        self.ifft_with_backend(worker, backend)?;
+        self.distribute_powers(worker, geninv)
+    }
+}
+
+pub(crate) fn best_fft<E: Engine, T: Group<E>>(a: &mut [T], worker: &Worker, omega: &E::Fr, log_n: u32) -> Result<(), SynthesisError>
 {
     let log_cpus = worker.log_num_cpus();
 
     if log_n <= log_cpus {
         serial_fft(a, omega, log_n);
+
+        Ok(())
     } else {
-        parallel_fft(a, worker, omega, log_n, log_cpus);
+        parallel_fft(a, worker, omega, log_n, log_cpus)
     }
 }
 
@@ -322,7 +351,7 @@ pub(crate) fn parallel_fft<E: Engine, T: Group<E>>(
     omega: &E::Fr,
     log_n: u32,
     log_cpus: u32
-)
+) -> Result<(), SynthesisError>
 {
     assert!(log_n >= log_cpus);
 
@@ -331,11 +360,11 @@ pub(crate) fn parallel_fft<E: Engine, T: Group<E>>(
     let mut tmp = vec![vec![T::group_zero(); 1 << log_new_n]; num_cpus];
     let new_omega = omega.pow(&[num_cpus as u64]);
 
-    worker.scope(0, |scope, _| {
+    worker.try_scope(0, |scope, _| {
         let a = &*a;
 
         for (j, tmp) in tmp.iter_mut().enumerate() {
-            scope.spawn(move |_| {
+            scope.spawn(move || {
                 // Shuffle into a sub-FFT
                 let omega_j = omega.pow(&[j as u64]);
                 let omega_step = omega.pow(&[(j as u64) << log_new_n]);
@@ -356,14 +385,14 @@ pub(crate) fn parallel_fft<E: Engine, T: Group<E>>(
                 serial_fft(tmp, &new_omega, log_new_n);
             });
         }
-    });
+    })?;
 
     // TODO: does this hurt or help?
-    worker.scope(a.len(), |scope, chunk| {
+    worker.try_scope(a.len(), |scope, chunk| {
         let tmp = &tmp;
 
         for (idx, a) in a.chunks_mut(chunk).enumerate() {
-            scope.spawn(move |_| {
+            scope.spawn(move || {
                 let mut idx = idx * chunk;
                 let mask = (1 << log_cpus) - 1;
                 for a in a {
@@ -372,7 +401,9 @@ pub(crate) fn parallel_fft<E: Engine, T: Group<E>>(
                 }
             });
         }
-    });
+    })?;
+
+    Ok(())
 }
 
 // Test multiplying various (low degree) polynomials together and
@@ -407,10 +438,10 @@ fn polynomial_arith() {
                 let mut a = EvaluationDomain::from_coeffs(a).unwrap();
                 let mut b = EvaluationDomain::from_coeffs(b).unwrap();
 
-                a.fft(&worker);
-                b.fft(&worker);
-                a.mul_assign(&worker, &b);
-                a.ifft(&worker);
+                a.fft(&worker).unwrap();
+                b.fft(&worker).unwrap();
+                a.mul_assign(&worker, &b).unwrap();
+                a.ifft(&worker).unwrap();
 
                 for (naive, fft) in naive.iter().zip(a.coeffs.iter()) {
                     assert!(naive == fft);
@@ -442,17 +473,17 @@ fn fft_composition() {
             }
 
             let mut domain = EvaluationDomain::from_coeffs(v.clone()).unwrap();
-            domain.ifft(&worker);
-            domain.fft(&worker);
+            domain.ifft(&worker).unwrap();
+            domain.fft(&worker).unwrap();
             assert!(v == domain.coeffs);
-            domain.fft(&worker);
-            domain.ifft(&worker);
+            domain.fft(&worker).unwrap();
+            domain.ifft(&worker).unwrap();
             assert!(v == domain.coeffs);
-            domain.icoset_fft(&worker);
-            domain.coset_fft(&worker);
+            domain.icoset_fft(&worker).unwrap();
+            domain.coset_fft(&worker).unwrap();
             assert!(v == domain.coeffs);
-            domain.coset_fft(&worker);
-            domain.icoset_fft(&worker);
+            domain.coset_fft(&worker).unwrap();
+            domain.icoset_fft(&worker).unwrap();
             assert!(v == domain.coeffs);
         }
     }
@@ -481,7 +512,7 @@ fn parallel_fft_consistency() {
                 let mut v2 = EvaluationDomain::from_coeffs(v1.coeffs.clone()).unwrap();
 
                 for log_cpus in log_d..min(log_d+1, 3) {
-                    parallel_fft(&mut v1.coeffs, &worker, &v1.omega, log_d, log_cpus);
+                    parallel_fft(&mut v1.coeffs, &worker, &v1.omega, log_d, log_cpus).unwrap();
                     serial_fft(&mut v2.coeffs, &v2.omega, log_d);
 
                     assert!(v1.coeffs == v2.coeffs);
@@ -516,7 +547,7 @@ fn test_field_element_multiplication_bn256() {
 
     let start = std::time::Instant::now();
 
-    v1.mul_assign(&pool, &v2);
+    v1.mul_assign(&pool, &v2).unwrap();
 
     let duration_ns = start.elapsed().as_nanos() as f64;
     println!("Elapsed {} ns for {} samples", duration_ns, SAMPLES);
@@ -543,7 +574,7 @@ fn test_fft_bn256() {
 
     let start = std::time::Instant::now();
 
-    v1.ifft(&pool);
+    v1.ifft(&pool).unwrap();
 
     let duration_ns = start.elapsed().as_nanos() as f64;
     println!("Elapsed {} ns for {} samples", duration_ns, SAMPLES);
@@ -479,7 +479,7 @@ pub fn generate_parameters<E, C>(
     let start = std::time::Instant::now();
 
     // Use inverse FFT to convert to intepolation coefficients
-    domain.ifft(&worker);
+    domain.ifft(&worker)?;
     let powers_of_tau = domain.into_coeffs();
     // domain is now a set of scalars
 
@@ -0,0 +1,113 @@
+//! Canonical encoding of verifying keys and proofs, e.g. for committing
+//! to a key on-chain. Unlike `write`, the encoding is fixed and will not
+//! change with the serialization formats of this crate.
+//!
+//! Points are in the compressed form of `CurveAffine::into_compressed` of
+//! the pairing crate, numbers are big-endian.
+
+use crate::pairing::{
+    Engine,
+    CurveAffine,
+    EncodedPoint
+};
+
+use super::{
+    Proof,
+    VerifyingKey
+};
+
+/// Version of the canonical encoding, its first byte
+pub const CANONICAL_VERSION: u8 = 1;
+
+impl<E: Engine> VerifyingKey<E> {
+    /// Encodes the key as
+    ///
+    /// - `version`: one byte, `CANONICAL_VERSION`
+    /// - `alpha_g1`, `beta_g1`: compressed G1 points
+    /// - `beta_g2`, `gamma_g2`: compressed G2 points
+    /// - `delta_g1`: compressed G1 point
+    /// - `delta_g2`: compressed G2 point
+    /// - `ic_len`: number of `ic` points as a 4-byte integer
+    /// - `ic`: `ic_len` compressed G1 points
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let g1_size = <E::G1Affine as CurveAffine>::Compressed::size();
+        let g2_size = <E::G2Affine as CurveAffine>::Compressed::size();
+
+        let mut bytes = Vec::with_capacity(1 + (3 + self.ic.len()) * g1_size + 3 * g2_size + 4);
+        bytes.push(CANONICAL_VERSION);
+        bytes.extend_from_slice(self.alpha_g1.into_compressed().as_ref());
+        bytes.extend_from_slice(self.beta_g1.into_compressed().as_ref());
+        bytes.extend_from_slice(self.beta_g2.into_compressed().as_ref());
+        bytes.extend_from_slice(self.gamma_g2.into_compressed().as_ref());
+        bytes.extend_from_slice(self.delta_g1.into_compressed().as_ref());
+        bytes.extend_from_slice(self.delta_g2.into_compressed().as_ref());
+        bytes.extend_from_slice(&(self.ic.len() as u32).to_be_bytes());
+        for ic in &self.ic {
+            bytes.extend_from_slice(ic.into_compressed().as_ref());
+        }
+
+        bytes
+    }
+
+    /// Hash of `canonical_bytes`, without any personalization, so it can
+    /// be recomputed from the bytes alone.
+    #[cfg(feature = "fingerprint")]
+    pub fn fingerprint<H: FingerprintHasher>(&self) -> [u8; 32] {
+        H::hash(&self.canonical_bytes())
+    }
+}
+
+impl<E: Engine> Proof<E> {
+    /// Encodes the proof as
+    ///
+    /// - `version`: one byte, `CANONICAL_VERSION`
+    /// - `a`: compressed G1 point
+    /// - `b`: compressed G2 point
+    /// - `c`: compressed G1 point
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let g1_size = <E::G1Affine as CurveAffine>::Compressed::size();
+        let g2_size = <E::G2Affine as CurveAffine>::Compressed::size();
+
+        let mut bytes = Vec::with_capacity(1 + 2 * g1_size + g2_size);
+        bytes.push(CANONICAL_VERSION);
+        bytes.extend_from_slice(self.a.into_compressed().as_ref());
+        bytes.extend_from_slice(self.b.into_compressed().as_ref());
+        bytes.extend_from_slice(self.c.into_compressed().as_ref());
+
+        bytes
+    }
+}
+
+/// A 32-byte hash used for fingerprints.
+#[cfg(feature = "fingerprint")]
+pub trait FingerprintHasher {
+    fn hash(data: &[u8]) -> [u8; 32];
+}
+
+#[cfg(feature = "fingerprint")]
+pub struct Blake2sFingerprint;
+
+#[cfg(feature = "fingerprint")]
+impl FingerprintHasher for Blake2sFingerprint {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(blake2_rfc::blake2s::blake2s(32, &[], data).as_bytes());
+
+        hash
+    }
+}
+
+#[cfg(feature = "fingerprint")]
+pub struct Keccak256Fingerprint;
+
+#[cfg(feature = "fingerprint")]
+impl FingerprintHasher for Keccak256Fingerprint {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        let mut keccak = tiny_keccak::Keccak::new_keccak256();
+        keccak.update(data);
+        keccak.finalize(&mut hash);
+
+        hash
+    }
+}
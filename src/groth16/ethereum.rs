@@ -0,0 +1,185 @@
+//! Encoding of verifying keys, proofs and public inputs in the format
+//! of the EVM precompiles (EIP-196/197) and the common Solidity verifier.
+//!
+//! Coordinates are 32-byte big-endian words, G1 points are (x, y) and
+//! G2 points are (x_c1, x_c0, y_c1, y_c0). The point at infinity is
+//! encoded as all zeroes, and all zeroes are decoded as the point at
+//! infinity, like the precompiles do. Only `Bn256` is supported, as it is the only
+//! engine whose base field fits into 256 bits.
+
+use crate::pairing::{
+    Engine,
+    CurveAffine,
+    EncodedPoint
+};
+
+use crate::pairing::ff::{
+    PrimeField,
+    PrimeFieldRepr
+};
+
+use crate::pairing::bn256::{
+    Bn256,
+    Fr,
+    FrRepr,
+    G1Affine,
+    G2Affine
+};
+
+use super::{
+    Proof,
+    VerifyingKey,
+    PreparedVerifyingKey
+};
+
+use std::io;
+
+/// Size of an encoded G1 point
+pub const ETH_G1_SIZE: usize = 64;
+/// Size of an encoded G2 point
+pub const ETH_G2_SIZE: usize = 128;
+/// Size of an encoded proof: A, B and C
+pub const ETH_PROOF_SIZE: usize = 2 * ETH_G1_SIZE + ETH_G2_SIZE;
+
+/// Verifying key in the layout of the Solidity verifier. It does not
+/// contain beta and delta in G1, so it can only be turned back into a
+/// `PreparedVerifyingKey`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EthVerifyingKey {
+    pub alpha_g1: [u8; ETH_G1_SIZE],
+    pub beta_g2: [u8; ETH_G2_SIZE],
+    pub gamma_g2: [u8; ETH_G2_SIZE],
+    pub delta_g2: [u8; ETH_G2_SIZE],
+    pub ic: Vec<[u8; ETH_G1_SIZE]>
+}
+
+impl EthVerifyingKey {
+    /// Parses and validates the points of the key.
+    pub fn prepare(&self) -> io::Result<PreparedVerifyingKey<Bn256>> {
+        let alpha_g1 = g1_from_eth(&self.alpha_g1)?;
+        let beta_g2 = g2_from_eth(&self.beta_g2)?;
+
+        let mut neg_gamma_g2 = g2_from_eth(&self.gamma_g2)?;
+        neg_gamma_g2.negate();
+        let mut neg_delta_g2 = g2_from_eth(&self.delta_g2)?;
+        neg_delta_g2.negate();
+
+        let ic = self.ic.iter().map(g1_from_eth).collect::<io::Result<Vec<_>>>()?;
+
+        Ok(PreparedVerifyingKey {
+            alpha_g1_beta_g2: Bn256::pairing(alpha_g1, beta_g2),
+            neg_gamma_g2: neg_gamma_g2.prepare(),
+            neg_delta_g2: neg_delta_g2.prepare(),
+            ic
+        })
+    }
+}
+
+impl VerifyingKey<Bn256> {
+    pub fn to_eth_abi(&self) -> EthVerifyingKey {
+        EthVerifyingKey {
+            alpha_g1: g1_to_eth(&self.alpha_g1),
+            beta_g2: g2_to_eth(&self.beta_g2),
+            gamma_g2: g2_to_eth(&self.gamma_g2),
+            delta_g2: g2_to_eth(&self.delta_g2),
+            ic: self.ic.iter().map(g1_to_eth).collect()
+        }
+    }
+}
+
+impl Proof<Bn256> {
+    pub fn to_eth_bytes(&self) -> [u8; ETH_PROOF_SIZE] {
+        let mut bytes = [0u8; ETH_PROOF_SIZE];
+        bytes[..ETH_G1_SIZE].copy_from_slice(&g1_to_eth(&self.a));
+        bytes[ETH_G1_SIZE..(ETH_G1_SIZE + ETH_G2_SIZE)].copy_from_slice(&g2_to_eth(&self.b));
+        bytes[(ETH_G1_SIZE + ETH_G2_SIZE)..].copy_from_slice(&g1_to_eth(&self.c));
+
+        bytes
+    }
+
+    pub fn from_eth_bytes(bytes: &[u8; ETH_PROOF_SIZE]) -> io::Result<Self> {
+        let mut a = [0u8; ETH_G1_SIZE];
+        a.copy_from_slice(&bytes[..ETH_G1_SIZE]);
+        let mut b = [0u8; ETH_G2_SIZE];
+        b.copy_from_slice(&bytes[ETH_G1_SIZE..(ETH_G1_SIZE + ETH_G2_SIZE)]);
+        let mut c = [0u8; ETH_G1_SIZE];
+        c.copy_from_slice(&bytes[(ETH_G1_SIZE + ETH_G2_SIZE)..]);
+
+        Ok(Proof {
+            a: g1_from_eth(&a)?,
+            b: g2_from_eth(&b)?,
+            c: g1_from_eth(&c)?
+        })
+    }
+}
+
+/// Encodes the public inputs as 32-byte big-endian words.
+pub fn public_inputs_to_eth(inputs: &[Fr]) -> Vec<[u8; 32]> {
+    inputs.iter().map(|input| {
+        let mut word = [0u8; 32];
+        input.into_repr().write_be(&mut word[..]).expect("word has the size of the repr");
+
+        word
+    }).collect()
+}
+
+/// Decodes the public inputs from 32-byte big-endian words. Words that
+/// are not smaller than the modulus of `Fr` are rejected, like the
+/// Solidity verifier does.
+pub fn public_inputs_from_eth(words: &[[u8; 32]]) -> io::Result<Vec<Fr>> {
+    words.iter().map(|word| {
+        let mut repr = FrRepr::default();
+        repr.read_be(&word[..])?;
+
+        Fr::from_repr(repr).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }).collect()
+}
+
+fn g1_to_eth(point: &G1Affine) -> [u8; ETH_G1_SIZE] {
+    let mut bytes = [0u8; ETH_G1_SIZE];
+    if !point.is_zero() {
+        bytes.copy_from_slice(point.into_uncompressed().as_ref());
+    }
+
+    bytes
+}
+
+fn g2_to_eth(point: &G2Affine) -> [u8; ETH_G2_SIZE] {
+    let mut bytes = [0u8; ETH_G2_SIZE];
+    if !point.is_zero() {
+        bytes.copy_from_slice(point.into_uncompressed().as_ref());
+    }
+
+    bytes
+}
+
+fn g1_from_eth(bytes: &[u8; ETH_G1_SIZE]) -> io::Result<G1Affine> {
+    let mut repr = <G1Affine as CurveAffine>::Uncompressed::empty();
+    repr.as_mut().copy_from_slice(&bytes[..]);
+
+    point_from_eth(repr)
+}
+
+fn g2_from_eth(bytes: &[u8; ETH_G2_SIZE]) -> io::Result<G2Affine> {
+    let mut repr = <G2Affine as CurveAffine>::Uncompressed::empty();
+    repr.as_mut().copy_from_slice(&bytes[..]);
+
+    point_from_eth(repr)
+}
+
+fn point_from_eth<P: EncodedPoint>(repr: P) -> io::Result<P::Affine> {
+    let bytes = repr.as_ref();
+
+    // our own encoding of infinity has a flag instead
+    if bytes.iter().all(|b| *b == 0) {
+        return Ok(P::Affine::zero());
+    }
+
+    // the top bits are flags of our own encoding, but they are never set
+    // for coordinates smaller than the modulus
+    if bytes[0] & 0xc0 != 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "coordinate is not smaller than the modulus"));
+    }
+
+    repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
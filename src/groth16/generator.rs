@@ -1,6 +1,6 @@
 use crate::log::Stopwatch;
 
-use rand::Rng;
+use rand::{Rng, SeedableRng, ChaChaRng};
 
 use std::sync::Arc;
 
@@ -67,6 +67,73 @@ pub fn generate_random_parameters<E, C, R>(
     )
 }
 
+/// Generates parameters that are fully determined by the `seed`, e.g. for
+/// tests and reproducible benchmarks. Anyone who knows the seed knows the
+/// toxic waste and can forge proofs, so the parameters are insecure and
+/// must never be used in production.
+///
+/// Every one of the generators and the secrets, in the order `g1`, `g2`,
+/// `alpha`, `beta`, `gamma`, `delta` and `tau`, is derived from its own hash
+/// of the seed, see `seeded_rng`, and sampled like in
+/// `generate_random_parameters`. The result does not depend on the number
+/// of threads.
+pub fn generate_insecure_parameters_from_seed<E, C>(
+    circuit: C,
+    seed: [u8; 32]
+) -> Result<Parameters<E>, SynthesisError>
+    where E: Engine, C: Circuit<E>
+{
+    generate_insecure_parameters_from_seed_with_worker(circuit, seed, Worker::global())
+}
+
+pub fn generate_insecure_parameters_from_seed_with_worker<E, C>(
+    circuit: C,
+    seed: [u8; 32],
+    worker: &Worker
+) -> Result<Parameters<E>, SynthesisError>
+    where E: Engine, C: Circuit<E>
+{
+    let g1 = seeded_rng(&seed, "g1").gen();
+    let g2 = seeded_rng(&seed, "g2").gen();
+    let alpha = seeded_rng(&seed, "alpha").gen();
+    let beta = seeded_rng(&seed, "beta").gen();
+    let gamma = seeded_rng(&seed, "gamma").gen();
+    let delta = seeded_rng(&seed, "delta").gen();
+    let tau = seeded_rng(&seed, "tau").gen();
+
+    generate_parameters_with_worker::<E, C>(
+        circuit,
+        g1,
+        g2,
+        alpha,
+        beta,
+        gamma,
+        delta,
+        tau,
+        worker
+    )
+}
+
+/// Separates the hashes of the seeded parameters from any other use of the seed.
+const SEEDED_PARAMETERS_DOMAIN: &[u8] = b"bellman_ce groth16 insecure parameters from seed: ";
+
+/// RNG for the secret `name` of `generate_insecure_parameters_from_seed`:
+/// ChaCha20 keyed with BLAKE2s-256, keyed with the `seed`, of
+/// `SEEDED_PARAMETERS_DOMAIN` followed by the `name`. The digest is read
+/// as eight little-endian 32-bit words.
+fn seeded_rng(seed: &[u8; 32], name: &str) -> ChaChaRng {
+    let mut data = SEEDED_PARAMETERS_DOMAIN.to_vec();
+    data.extend_from_slice(name.as_bytes());
+    let hash = blake2_rfc::blake2s::blake2s(32, seed, &data);
+
+    let mut key = [0u32; 8];
+    for (word, bytes) in key.iter_mut().zip(hash.as_bytes().chunks(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+
+    ChaChaRng::from_seed(&key[..])
+}
+
 /// This is our assembly structure that we'll use to synthesize the
 /// circuit into a QAP.
 struct KeypairAssembly<E: Engine> {
@@ -84,6 +151,8 @@ struct KeypairAssembly<E: Engine> {
 impl<E: Engine> ConstraintSystem<E> for KeypairAssembly<E> {
     type Root = Self;
 
+    const WANTS_ANNOTATIONS: bool = false;
+
     fn alloc<F, A, AR>(
         &mut self,
         _: A,
@@ -186,6 +255,25 @@ pub fn generate_parameters<E, C>(
     tau: E::Fr
 ) -> Result<Parameters<E>, SynthesisError>
     where E: Engine, C: Circuit<E>
+{
+    generate_parameters_with_worker::<E, C>(circuit, g1, g2, alpha, beta, gamma, delta, tau, Worker::global())
+}
+
+/// Same as `generate_parameters`, but runs on the given `worker`. The
+/// result does not depend on the number of threads of the worker.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_parameters_with_worker<E, C>(
+    circuit: C,
+    g1: E::G1,
+    g2: E::G2,
+    alpha: E::Fr,
+    beta: E::Fr,
+    gamma: E::Fr,
+    delta: E::Fr,
+    tau: E::Fr,
+    worker: &Worker
+) -> Result<Parameters<E>, SynthesisError>
+    where E: Engine, C: Circuit<E>
 {
     let mut assembly = KeypairAssembly {
         num_inputs: 0,
@@ -243,8 +331,6 @@ pub fn generate_parameters<E, C>(
     let gamma_inverse = gamma.inverse().ok_or(SynthesisError::UnexpectedIdentity)?;
     let delta_inverse = delta.inverse().ok_or(SynthesisError::UnexpectedIdentity)?;
 
-    let worker = Worker::new();
-
     let mut h = vec![E::G1::zero(); powers_of_tau.as_ref().len() - 1];
     {
         // Compute powers of tau
@@ -254,10 +340,10 @@ pub fn generate_parameters<E, C>(
 
         {
             let powers_of_tau = powers_of_tau.as_mut();
-            worker.scope(powers_of_tau.len(), |scope, chunk| {
+            worker.try_scope(powers_of_tau.len(), |scope, chunk| {
                 for (i, powers_of_tau) in powers_of_tau.chunks_mut(chunk).enumerate()
                 {
-                    scope.spawn(move |_| {
+                    scope.spawn(move || {
                         let mut current_tau_power = tau.pow(&[(i*chunk) as u64]);
 
                         for p in powers_of_tau {
@@ -266,7 +352,7 @@ pub fn generate_parameters<E, C>(
                         }
                     });
                 }
-            });
+            })?;
         }
         elog_verbose!("powers of tau stage 1 done in {} s", stopwatch.elapsed());
 
@@ -279,11 +365,11 @@ pub fn generate_parameters<E, C>(
         let stopwatch = Stopwatch::new();
 
         // Compute the H query with multiple threads
-        worker.scope(h.len(), |scope, chunk| {
+        worker.try_scope(h.len(), |scope, chunk| {
             for (h, p) in h.chunks_mut(chunk).zip(powers_of_tau.as_ref().chunks(chunk))
             {
                 let mut g1_wnaf = g1_wnaf.shared();
-                scope.spawn(move |_| {
+                scope.spawn(move || {
                     // Set values of the H query to g1^{(tau^i * t(tau)) / delta}
                     for (h, p) in h.iter_mut().zip(p.iter())
                     {
@@ -299,7 +385,7 @@ pub fn generate_parameters<E, C>(
                     E::G1::batch_normalization(h);
                 });
             }
-        });
+        })?;
         elog_verbose!("computing the H query done in {} s", stopwatch.elapsed());
     }
 
@@ -308,7 +394,7 @@ pub fn generate_parameters<E, C>(
     let stopwatch = Stopwatch::new();
 
     // Use inverse FFT to convert powers of tau to Lagrange coefficients
-    powers_of_tau.ifft(&worker);
+    powers_of_tau.ifft(worker)?;
     let powers_of_tau = powers_of_tau.into_coeffs();
 
     elog_verbose!("powers of tau stage 2 done in {} s", stopwatch.elapsed());
@@ -349,7 +435,7 @@ pub fn generate_parameters<E, C>(
 
         // Worker
         worker: &Worker
-    )
+    ) -> Result<(), SynthesisError>
 
     {
         // Sanity check
@@ -361,7 +447,7 @@ pub fn generate_parameters<E, C>(
         assert_eq!(a.len(), ext.len());
 
         // Evaluate polynomials in multiple threads
-        worker.scope(a.len(), |scope, chunk| {
+        worker.try_scope(a.len(), |scope, chunk| {
             for ((((((a, b_g1), b_g2), ext), at), bt), ct) in a.chunks_mut(chunk)
                                                                .zip(b_g1.chunks_mut(chunk))
                                                                .zip(b_g2.chunks_mut(chunk))
@@ -373,7 +459,7 @@ pub fn generate_parameters<E, C>(
                 let mut g1_wnaf = g1_wnaf.shared();
                 let mut g2_wnaf = g2_wnaf.shared();
 
-                scope.spawn(move |_| {
+                scope.spawn(move || {
                     for ((((((a, b_g1), b_g2), ext), at), bt), ct) in a.iter_mut()
                                                                        .zip(b_g1.iter_mut())
                                                                        .zip(b_g2.iter_mut())
@@ -433,7 +519,9 @@ pub fn generate_parameters<E, C>(
                     E::G1::batch_normalization(ext);
                 });
             };
-        });
+        })?;
+
+        Ok(())
     }
 
     // Evaluate for inputs.
@@ -451,8 +539,8 @@ pub fn generate_parameters<E, C>(
         &gamma_inverse,
         &alpha,
         &beta,
-        &worker
-    );
+        worker
+    )?;
 
     // Evaluate for auxillary variables.
     eval(
@@ -469,8 +557,8 @@ pub fn generate_parameters<E, C>(
         &delta_inverse,
         &alpha,
         &beta,
-        &worker
-    );
+        worker
+    )?;
 
     elog_verbose!("evaluating polynomials done in {} s", stopwatch.elapsed());
 
@@ -0,0 +1,367 @@
+use crate::pairing::{
+    Engine,
+    CurveAffine,
+    CurveProjective,
+    EncodedPoint
+};
+
+use crate::{
+    SynthesisError
+};
+
+use crate::source::{
+    SourceBuilder,
+    Source
+};
+
+use super::{
+    Parameters,
+    ParameterSource,
+    VerifyingKey
+};
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use byteorder::{BigEndian, WriteBytesExt, ReadBytesExt};
+
+/// Number of sections with bases in the parameters: H, L, A, B in G1, B in G2
+const NUM_SECTIONS: usize = 5;
+const INDEX_SIZE: u64 = (NUM_SECTIONS * 8) as u64;
+
+/// When the points of `IndexedParameters` are validated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointValidation {
+    /// Points are trusted and are only checked to be not at infinity.
+    Unchecked,
+    /// All points are validated once when the parameters are opened.
+    Upfront,
+    /// Every point is validated when it is read by a multiexp.
+    OnRead
+}
+
+impl<E: Engine> Parameters<E> {
+    /// Same as `write`, followed by an index of section offsets
+    /// that is used by `IndexedParameters`. The result can still be
+    /// parsed by `read`.
+    pub fn write_indexed<W: Write>(
+        &self,
+        mut writer: W
+    ) -> io::Result<()>
+    {
+        let mut vk = vec![];
+        self.vk.write(&mut vk)?;
+
+        let g1_size = <E::G1Affine as CurveAffine>::Uncompressed::size() as u64;
+        let g2_size = <E::G2Affine as CurveAffine>::Uncompressed::size() as u64;
+
+        let mut offsets = vec![];
+        let mut offset = vk.len() as u64;
+        for &(len, size) in &[
+            (self.h.len(), g1_size),
+            (self.l.len(), g1_size),
+            (self.a.len(), g1_size),
+            (self.b_g1.len(), g1_size),
+            (self.b_g2.len(), g2_size)
+        ] {
+            offsets.push(offset);
+            offset += 4 + (len as u64) * size;
+        }
+
+        self.write(&mut writer)?;
+
+        for offset in offsets {
+            writer.write_u64::<BigEndian>(offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parameters that stay in a file written by `Parameters::write_indexed`.
+/// Bases are read lazily when a multiexp requests them, so the proving key
+/// does not have to fit into memory.
+///
+/// The file is not memory-mapped, the bases are streamed from it instead.
+/// Every `IndexedSource` opens the file on its first read, and a multiexp
+/// reads its section once for every window of the exponents, e.g. about 20
+/// times for a million of bases. Each read point is parsed again as well,
+/// so when the proving key fits into memory and many proofs are created,
+/// `Parameters::read` is faster.
+pub struct IndexedParameters<E: Engine> {
+    path: Arc<PathBuf>,
+    vk: VerifyingKey<E>,
+    validation: PointValidation,
+    // byte offset of the first point and the number of points for every section
+    sections: [(u64, usize); NUM_SECTIONS]
+}
+
+impl<E: Engine> IndexedParameters<E> {
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        validation: PointValidation
+    ) -> io::Result<Self>
+    {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path)?;
+        let file_len = file.metadata()?.len();
+
+        if file_len < INDEX_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "file is too short to contain an index"));
+        }
+
+        let vk = VerifyingKey::<E>::read(BufReader::new(&mut file))?;
+
+        file.seek(SeekFrom::Start(file_len - INDEX_SIZE))?;
+        let mut offsets = [0u64; NUM_SECTIONS];
+        for offset in offsets.iter_mut() {
+            *offset = file.read_u64::<BigEndian>()?;
+        }
+
+        let g1_size = <E::G1Affine as CurveAffine>::Uncompressed::size() as u64;
+        let g2_size = <E::G2Affine as CurveAffine>::Uncompressed::size() as u64;
+
+        // the sections start right after the verifying key
+        let mut vk_bytes = vec![];
+        vk.write(&mut vk_bytes)?;
+
+        let mut sections = [(0u64, 0usize); NUM_SECTIONS];
+        let mut end = vk_bytes.len() as u64;
+        for (i, &offset) in offsets.iter().enumerate() {
+            if offset != end {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "sections of the index are not contiguous"));
+            }
+
+            file.seek(SeekFrom::Start(offset))?;
+            let len = file.read_u32::<BigEndian>()? as usize;
+            let size = if i == NUM_SECTIONS - 1 { g2_size } else { g1_size };
+
+            sections[i] = (offset + 4, len);
+            end = offset + 4 + (len as u64) * size;
+        }
+
+        if end != file_len - INDEX_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "index does not match the length of the file"));
+        }
+
+        let params = IndexedParameters {
+            path: Arc::new(path),
+            vk,
+            validation,
+            sections
+        };
+
+        if validation == PointValidation::Upfront {
+            params.validate()?;
+        }
+
+        Ok(params)
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<E> {
+        &self.vk
+    }
+
+    fn validate(&self) -> io::Result<()> {
+        let mut file = BufReader::new(File::open(&*self.path)?);
+
+        for (i, &(offset, len)) in self.sections.iter().enumerate() {
+            file.seek(SeekFrom::Start(offset))?;
+            for _ in 0..len {
+                if i == NUM_SECTIONS - 1 {
+                    read_point::<E::G2Affine, _>(&mut file, true)?;
+                } else {
+                    read_point::<E::G1Affine, _>(&mut file, true)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn source<G: CurveAffine>(&self, section: usize, start: usize) -> IndexedSource<G> {
+        let (offset, len) = self.sections[section];
+
+        IndexedSource {
+            path: self.path.clone(),
+            offset,
+            len,
+            position: start,
+            checked: self.validation == PointValidation::OnRead,
+            reader: None,
+            _marker: PhantomData
+        }
+    }
+}
+
+fn read_point<G: CurveAffine, R: Read>(reader: &mut R, checked: bool) -> io::Result<G> {
+    let mut repr = G::Uncompressed::empty();
+    reader.read_exact(repr.as_mut())?;
+
+    if checked {
+        repr
+        .into_affine()
+    } else {
+        repr
+        .into_affine_unchecked()
+    }
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    .and_then(|e| if e.is_zero() {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "point at infinity"))
+    } else {
+        Ok(e)
+    })
+}
+
+/// Source of bases that reads one section of the `IndexedParameters` file.
+/// The file is opened on the first read, so every clone of the source has
+/// its own file handle and buffer.
+pub struct IndexedSource<G: CurveAffine> {
+    path: Arc<PathBuf>,
+    offset: u64,
+    len: usize,
+    position: usize,
+    checked: bool,
+    reader: Option<BufReader<File>>,
+    _marker: PhantomData<G>
+}
+
+impl<G: CurveAffine> Clone for IndexedSource<G> {
+    fn clone(&self) -> Self {
+        IndexedSource {
+            path: self.path.clone(),
+            offset: self.offset,
+            len: self.len,
+            position: self.position,
+            checked: self.checked,
+            reader: None,
+            _marker: PhantomData
+        }
+    }
+}
+
+impl<G: CurveAffine> SourceBuilder<G> for IndexedSource<G> {
+    type Source = Self;
+
+    fn new(self) -> Self {
+        self
+    }
+}
+
+impl<G: CurveAffine> Source<G> for IndexedSource<G> {
+    fn add_assign_mixed(&mut self, to: &mut <G as CurveAffine>::Projective) -> Result<(), SynthesisError> {
+        if self.len <= self.position {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "expected more bases when adding from source").into());
+        }
+
+        let checked = self.checked;
+        let point = read_point::<G, _>(self.reader()?, checked)?;
+
+        to.add_assign_mixed(&point);
+
+        self.position += 1;
+
+        Ok(())
+    }
+
+    fn skip(&mut self, amt: usize) -> Result<(), SynthesisError> {
+        if self.len <= self.position {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "expected more bases skipping from source").into());
+        }
+
+        if let Some(reader) = self.reader.as_mut() {
+            let size = G::Uncompressed::size() as i64;
+            reader.seek_relative((amt as i64) * size)?;
+        }
+
+        self.position += amt;
+
+        Ok(())
+    }
+
+    fn read_bases(&mut self, amt: usize) -> Result<Vec<G>, SynthesisError> {
+        if self.len < self.position + amt {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "expected more bases when reading from source").into());
+        }
+
+        let checked = self.checked;
+        let reader = self.reader()?;
+        let bases = (0..amt).map(|_| read_point::<G, _>(reader, checked)).collect::<io::Result<Vec<_>>>()?;
+
+        self.position += amt;
+
+        Ok(bases)
+    }
+}
+
+impl<G: CurveAffine> IndexedSource<G> {
+    /// Opens the file at the current position on the first read.
+    fn reader(&mut self) -> io::Result<&mut BufReader<File>> {
+        if self.reader.is_none() {
+            let size = G::Uncompressed::size() as u64;
+            let mut reader = BufReader::new(File::open(&*self.path)?);
+            reader.seek(SeekFrom::Start(self.offset + (self.position as u64) * size))?;
+            self.reader = Some(reader);
+        }
+
+        Ok(self.reader.as_mut().expect("reader is opened"))
+    }
+}
+
+impl<E: Engine> ParameterSource<E> for &IndexedParameters<E> {
+    type G1Builder = IndexedSource<E::G1Affine>;
+    type G2Builder = IndexedSource<E::G2Affine>;
+
+    fn get_vk(
+        &mut self,
+        _: usize
+    ) -> Result<VerifyingKey<E>, SynthesisError>
+    {
+        Ok(self.vk.clone())
+    }
+
+    fn get_h(
+        &mut self,
+        _: usize
+    ) -> Result<Self::G1Builder, SynthesisError>
+    {
+        Ok(self.source(0, 0))
+    }
+
+    fn get_l(
+        &mut self,
+        _: usize
+    ) -> Result<Self::G1Builder, SynthesisError>
+    {
+        Ok(self.source(1, 0))
+    }
+
+    fn get_a(
+        &mut self,
+        num_inputs: usize,
+        _: usize
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError>
+    {
+        Ok((self.source(2, 0), self.source(2, num_inputs)))
+    }
+
+    fn get_b_g1(
+        &mut self,
+        num_inputs: usize,
+        _: usize
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError>
+    {
+        Ok((self.source(3, 0), self.source(3, num_inputs)))
+    }
+
+    fn get_b_g2(
+        &mut self,
+        num_inputs: usize,
+        _: usize
+    ) -> Result<(Self::G2Builder, Self::G2Builder), SynthesisError>
+    {
+        Ok((self.source(4, 0), self.source(4, num_inputs)))
+    }
+}
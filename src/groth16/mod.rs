@@ -19,10 +19,17 @@ mod tests;
 mod generator;
 mod prover;
 mod verifier;
+mod indexed;
+mod parameters_verifier;
+pub mod ethereum;
+mod canonical;
 
 pub use self::generator::*;
 pub use self::prover::*;
 pub use self::verifier::*;
+pub use self::indexed::*;
+pub use self::parameters_verifier::*;
+pub use self::canonical::*;
 
 #[derive(Debug, Clone)]
 pub struct Proof<E: Engine> {
@@ -42,52 +49,77 @@ impl<E: Engine> PartialEq for Proof<E> {
 impl<E: Engine> Proof<E> {
     pub fn write<W: Write>(
         &self,
-        mut writer: W
+        writer: W
     ) -> io::Result<()>
     {
-        writer.write_all(self.a.into_compressed().as_ref())?;
-        writer.write_all(self.b.into_compressed().as_ref())?;
-        writer.write_all(self.c.into_compressed().as_ref())?;
-
-        Ok(())
+        self.write_encoded::<<E::G1Affine as CurveAffine>::Compressed, <E::G2Affine as CurveAffine>::Compressed, _>(writer)
     }
 
     pub fn read<R: Read>(
-        mut reader: R
+        reader: R
     ) -> io::Result<Self>
     {
-        let mut g1_repr = <E::G1Affine as CurveAffine>::Compressed::empty();
-        let mut g2_repr = <E::G2Affine as CurveAffine>::Compressed::empty();
+        Self::read_encoded::<<E::G1Affine as CurveAffine>::Compressed, <E::G2Affine as CurveAffine>::Compressed, _>(reader, true)
+    }
 
-        reader.read_exact(g1_repr.as_mut())?;
-        let a = g1_repr
-                .into_affine()
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
-                .and_then(|e| if e.is_zero() {
-                    Err(io::Error::new(io::ErrorKind::InvalidData, "point at infinity"))
-                } else {
-                    Ok(e)
-                })?;
+    /// Same as `read`, but skips the subgroup checks. Points are still on
+    /// the curve. Must only be used for trusted input, e.g. the proofs
+    /// that were written by ourselves.
+    pub fn read_unchecked<R: Read>(
+        reader: R
+    ) -> io::Result<Self>
+    {
+        Self::read_encoded::<<E::G1Affine as CurveAffine>::Compressed, <E::G2Affine as CurveAffine>::Compressed, _>(reader, false)
+    }
 
-        reader.read_exact(g2_repr.as_mut())?;
-        let b = g2_repr
-                .into_affine()
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
-                .and_then(|e| if e.is_zero() {
-                    Err(io::Error::new(io::ErrorKind::InvalidData, "point at infinity"))
-                } else {
-                    Ok(e)
-                })?;
+    /// Writes the points uncompressed, which is larger, but is faster to read.
+    pub fn write_uncompressed<W: Write>(
+        &self,
+        writer: W
+    ) -> io::Result<()>
+    {
+        self.write_encoded::<<E::G1Affine as CurveAffine>::Uncompressed, <E::G2Affine as CurveAffine>::Uncompressed, _>(writer)
+    }
 
-        reader.read_exact(g1_repr.as_mut())?;
-        let c = g1_repr
-                .into_affine()
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
-                .and_then(|e| if e.is_zero() {
-                    Err(io::Error::new(io::ErrorKind::InvalidData, "point at infinity"))
-                } else {
-                    Ok(e)
-                })?;
+    pub fn read_uncompressed<R: Read>(
+        reader: R
+    ) -> io::Result<Self>
+    {
+        Self::read_encoded::<<E::G1Affine as CurveAffine>::Uncompressed, <E::G2Affine as CurveAffine>::Uncompressed, _>(reader, true)
+    }
+
+    /// Same as `read_uncompressed`, but skips the subgroup checks. Points
+    /// are still checked to be on the curve. Must only be used for trusted
+    /// input, e.g. the proofs that were written by ourselves.
+    pub fn read_uncompressed_unchecked<R: Read>(
+        reader: R
+    ) -> io::Result<Self>
+    {
+        Self::read_encoded::<<E::G1Affine as CurveAffine>::Uncompressed, <E::G2Affine as CurveAffine>::Uncompressed, _>(reader, false)
+    }
+
+    fn write_encoded<G1, G2, W>(
+        &self,
+        mut writer: W
+    ) -> io::Result<()>
+        where G1: EncodedPoint<Affine = E::G1Affine>, G2: EncodedPoint<Affine = E::G2Affine>, W: Write
+    {
+        writer.write_all(G1::from_affine(self.a).as_ref())?;
+        writer.write_all(G2::from_affine(self.b).as_ref())?;
+        writer.write_all(G1::from_affine(self.c).as_ref())?;
+
+        Ok(())
+    }
+
+    fn read_encoded<G1, G2, R>(
+        mut reader: R,
+        checked: bool
+    ) -> io::Result<Self>
+        where G1: EncodedPoint<Affine = E::G1Affine>, G2: EncodedPoint<Affine = E::G2Affine>, R: Read
+    {
+        let a = read_point::<G1, _>(&mut reader, checked).and_then(non_zero)?;
+        let b = read_point::<G2, _>(&mut reader, checked).and_then(non_zero)?;
+        let c = read_point::<G1, _>(&mut reader, checked).and_then(non_zero)?;
 
         Ok(Proof {
             a: a,
@@ -97,6 +129,43 @@ impl<E: Engine> Proof<E> {
     }
 }
 
+fn read_point<P: EncodedPoint, R: Read>(
+    reader: &mut R,
+    checked: bool
+) -> io::Result<P::Affine>
+{
+    let mut repr = P::empty();
+    reader.read_exact(repr.as_mut())?;
+
+    if checked {
+        return repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+    }
+
+    let point = repr.into_affine_unchecked().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    // Decompression always gives a point on the curve. The uncompressed
+    // encoding is not checked at all, so the point must survive being
+    // compressed and decompressed again, which is still much cheaper than
+    // the subgroup check.
+    let is_compressed = P::size() == <<P::Affine as CurveAffine>::Compressed as EncodedPoint>::size();
+    if !is_compressed && !point.is_zero() {
+        let on_curve = point.into_compressed().into_affine_unchecked().map(|p| p == point).unwrap_or(false);
+        if !on_curve {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "point is not on the curve"));
+        }
+    }
+
+    Ok(point)
+}
+
+fn non_zero<G: CurveAffine>(point: G) -> io::Result<G> {
+    if point.is_zero() {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "point at infinity"))
+    } else {
+        Ok(point)
+    }
+}
+
 #[derive(Clone)]
 pub struct VerifyingKey<E: Engine> {
     // alpha in g1 for verifying and for creating A/C elements of
@@ -140,62 +209,94 @@ impl<E: Engine> PartialEq for VerifyingKey<E> {
 impl<E: Engine> VerifyingKey<E> {
     pub fn write<W: Write>(
         &self,
-        mut writer: W
+        writer: W
     ) -> io::Result<()>
     {
-        writer.write_all(self.alpha_g1.into_uncompressed().as_ref())?;
-        writer.write_all(self.beta_g1.into_uncompressed().as_ref())?;
-        writer.write_all(self.beta_g2.into_uncompressed().as_ref())?;
-        writer.write_all(self.gamma_g2.into_uncompressed().as_ref())?;
-        writer.write_all(self.delta_g1.into_uncompressed().as_ref())?;
-        writer.write_all(self.delta_g2.into_uncompressed().as_ref())?;
-        writer.write_u32::<BigEndian>(self.ic.len() as u32)?;
-        for ic in &self.ic {
-            writer.write_all(ic.into_uncompressed().as_ref())?;
-        }
-
-        Ok(())
+        self.write_encoded::<<E::G1Affine as CurveAffine>::Uncompressed, <E::G2Affine as CurveAffine>::Uncompressed, _>(writer)
     }
 
     pub fn read<R: Read>(
-        mut reader: R
+        reader: R
     ) -> io::Result<Self>
     {
-        let mut g1_repr = <E::G1Affine as CurveAffine>::Uncompressed::empty();
-        let mut g2_repr = <E::G2Affine as CurveAffine>::Uncompressed::empty();
+        Self::read_encoded::<<E::G1Affine as CurveAffine>::Uncompressed, <E::G2Affine as CurveAffine>::Uncompressed, _>(reader, true)
+    }
 
-        reader.read_exact(g1_repr.as_mut())?;
-        let alpha_g1 = g1_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    /// Same as `read`, but skips the subgroup checks. Points are still
+    /// checked to be on the curve. Must only be used for trusted input,
+    /// e.g. the keys that were written by ourselves.
+    pub fn read_unchecked<R: Read>(
+        reader: R
+    ) -> io::Result<Self>
+    {
+        Self::read_encoded::<<E::G1Affine as CurveAffine>::Uncompressed, <E::G2Affine as CurveAffine>::Uncompressed, _>(reader, false)
+    }
 
-        reader.read_exact(g1_repr.as_mut())?;
-        let beta_g1 = g1_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    /// Writes the points compressed, which is smaller, but is slower to read.
+    pub fn write_compressed<W: Write>(
+        &self,
+        writer: W
+    ) -> io::Result<()>
+    {
+        self.write_encoded::<<E::G1Affine as CurveAffine>::Compressed, <E::G2Affine as CurveAffine>::Compressed, _>(writer)
+    }
 
-        reader.read_exact(g2_repr.as_mut())?;
-        let beta_g2 = g2_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    pub fn read_compressed<R: Read>(
+        reader: R
+    ) -> io::Result<Self>
+    {
+        Self::read_encoded::<<E::G1Affine as CurveAffine>::Compressed, <E::G2Affine as CurveAffine>::Compressed, _>(reader, true)
+    }
+
+    /// Same as `read_compressed`, but skips the subgroup checks. Points
+    /// are still on the curve. Must only be used for trusted input,
+    /// e.g. the keys that were written by ourselves.
+    pub fn read_compressed_unchecked<R: Read>(
+        reader: R
+    ) -> io::Result<Self>
+    {
+        Self::read_encoded::<<E::G1Affine as CurveAffine>::Compressed, <E::G2Affine as CurveAffine>::Compressed, _>(reader, false)
+    }
 
-        reader.read_exact(g2_repr.as_mut())?;
-        let gamma_g2 = g2_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fn write_encoded<G1, G2, W>(
+        &self,
+        mut writer: W
+    ) -> io::Result<()>
+        where G1: EncodedPoint<Affine = E::G1Affine>, G2: EncodedPoint<Affine = E::G2Affine>, W: Write
+    {
+        writer.write_all(G1::from_affine(self.alpha_g1).as_ref())?;
+        writer.write_all(G1::from_affine(self.beta_g1).as_ref())?;
+        writer.write_all(G2::from_affine(self.beta_g2).as_ref())?;
+        writer.write_all(G2::from_affine(self.gamma_g2).as_ref())?;
+        writer.write_all(G1::from_affine(self.delta_g1).as_ref())?;
+        writer.write_all(G2::from_affine(self.delta_g2).as_ref())?;
+        writer.write_u32::<BigEndian>(self.ic.len() as u32)?;
+        for ic in &self.ic {
+            writer.write_all(G1::from_affine(*ic).as_ref())?;
+        }
 
-        reader.read_exact(g1_repr.as_mut())?;
-        let delta_g1 = g1_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(())
+    }
 
-        reader.read_exact(g2_repr.as_mut())?;
-        let delta_g2 = g2_repr.into_affine().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fn read_encoded<G1, G2, R>(
+        mut reader: R,
+        checked: bool
+    ) -> io::Result<Self>
+        where G1: EncodedPoint<Affine = E::G1Affine>, G2: EncodedPoint<Affine = E::G2Affine>, R: Read
+    {
+        let alpha_g1 = read_point::<G1, _>(&mut reader, checked)?;
+        let beta_g1 = read_point::<G1, _>(&mut reader, checked)?;
+        let beta_g2 = read_point::<G2, _>(&mut reader, checked)?;
+        let gamma_g2 = read_point::<G2, _>(&mut reader, checked)?;
+        let delta_g1 = read_point::<G1, _>(&mut reader, checked)?;
+        let delta_g2 = read_point::<G2, _>(&mut reader, checked)?;
 
         let ic_len = reader.read_u32::<BigEndian>()? as usize;
 
         let mut ic = vec![];
 
         for _ in 0..ic_len {
-            reader.read_exact(g1_repr.as_mut())?;
-            let g1 = g1_repr
-                     .into_affine()
-                     .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
-                     .and_then(|e| if e.is_zero() {
-                         Err(io::Error::new(io::ErrorKind::InvalidData, "point at infinity"))
-                     } else {
-                         Ok(e)
-                     })?;
+            let g1 = read_point::<G1, _>(&mut reader, checked).and_then(non_zero)?;
 
             ic.push(g1);
         }
@@ -0,0 +1,429 @@
+use crate::pairing::{
+    Engine,
+    CurveProjective,
+    CurveAffine
+};
+
+use crate::pairing::ff::{
+    Field,
+    PrimeField
+};
+
+use rand::{Rand, Rng};
+
+use std::error::Error;
+use std::fmt;
+
+use super::{
+    Parameters
+};
+
+use crate::{
+    SynthesisError,
+    Circuit,
+    ConstraintSystem,
+    LinearCombination,
+    Variable,
+    Index
+};
+
+use crate::domain::{
+    EvaluationDomain,
+    Scalar
+};
+
+use crate::source::{
+    DensityTracker,
+    QueryDensity
+};
+
+use crate::multiexp::dense_multiexp;
+use crate::worker::Worker;
+
+/// Number of randomly chosen points of every query that are checked
+/// to be in the prime order subgroup.
+const SUBGROUP_SAMPLES: usize = 32;
+
+/// This is an error that could occur during the verification
+/// of the parameters against a circuit. No error is reported for the H
+/// query and for the IC and L points of the variables used in C, as
+/// `verify_parameters` can not check their values.
+#[derive(Debug)]
+pub enum ParameterError {
+    /// Synthesis of the circuit has failed
+    Synthesis(SynthesisError),
+    /// The query has a different number of points than the circuit needs
+    ShapeMismatch {
+        query: &'static str,
+        expected: usize,
+        got: usize
+    },
+    /// The point of the query is the point at infinity or is not in the prime order subgroup
+    InvalidPoint {
+        query: &'static str,
+        index: usize
+    },
+    /// The points are not consistent with each other, among the relations
+    /// that can be checked without the secrets
+    InconsistentPairing(&'static str)
+}
+
+impl From<SynthesisError> for ParameterError {
+    fn from(e: SynthesisError) -> ParameterError {
+        ParameterError::Synthesis(e)
+    }
+}
+
+impl Error for ParameterError {
+    fn description(&self) -> &str {
+        match *self {
+            ParameterError::Synthesis(_) => "circuit synthesis has failed",
+            ParameterError::ShapeMismatch { .. } => "parameters do not match the shape of the circuit",
+            ParameterError::InvalidPoint { .. } => "parameters contain an invalid point",
+            ParameterError::InconsistentPairing(_) => "parameters are not consistent"
+        }
+    }
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            ParameterError::Synthesis(ref e) => write!(f, "circuit synthesis has failed: {}", e),
+            ParameterError::ShapeMismatch { query, expected, got } => {
+                write!(f, "{} query has {} points, but the circuit needs {}", query, got, expected)
+            },
+            ParameterError::InvalidPoint { query, index } => {
+                write!(f, "point {} of {} query is invalid", index, query)
+            },
+            ParameterError::InconsistentPairing(what) => write!(f, "{} are not consistent", what)
+        }
+    }
+}
+
+/// Counts the variables and constraints of the circuit and tracks
+/// in which of A, B, C the variables are used.
+struct ShapeAssembly {
+    num_inputs: usize,
+    num_aux: usize,
+    num_constraints: usize,
+    a_aux_density: DensityTracker,
+    b_input_density: DensityTracker,
+    b_aux_density: DensityTracker,
+    c_input_density: DensityTracker,
+    c_aux_density: DensityTracker
+}
+
+impl<E: Engine> ConstraintSystem<E> for ShapeAssembly {
+    type Root = Self;
+
+    const WANTS_ANNOTATIONS: bool = false;
+
+    fn alloc<F, A, AR>(
+        &mut self,
+        _: A,
+        _: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let index = self.num_aux;
+        self.num_aux += 1;
+
+        self.a_aux_density.add_element();
+        self.b_aux_density.add_element();
+        self.c_aux_density.add_element();
+
+        Ok(Variable(Index::Aux(index)))
+    }
+
+    fn alloc_input<F, A, AR>(
+        &mut self,
+        _: A,
+        _: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let index = self.num_inputs;
+        self.num_inputs += 1;
+
+        self.b_input_density.add_element();
+        self.c_input_density.add_element();
+
+        Ok(Variable(Index::Input(index)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(
+        &mut self,
+        _: A,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where A: FnOnce() -> AR, AR: Into<String>,
+              LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        fn track<E: Engine>(
+            lc: LinearCombination<E>,
+            mut input_density: Option<&mut DensityTracker>,
+            aux_density: &mut DensityTracker
+        )
+        {
+            for (var, _) in lc.0 {
+                match var {
+                    Variable(Index::Input(i)) => {
+                        if let Some(ref mut d) = input_density {
+                            d.inc(i);
+                        }
+                    },
+                    Variable(Index::Aux(i)) => aux_density.inc(i)
+                }
+            }
+        }
+
+        // Inputs have full density in the A query
+        track(a(LinearCombination::zero()), None, &mut self.a_aux_density);
+        track(b(LinearCombination::zero()), Some(&mut self.b_input_density), &mut self.b_aux_density);
+        track(c(LinearCombination::zero()), Some(&mut self.c_input_density), &mut self.c_aux_density);
+
+        self.num_constraints += 1;
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+        where NR: Into<String>, N: FnOnce() -> NR
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self)
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+fn is_valid_point<G: CurveAffine>(p: &G) -> bool {
+    !p.is_zero() && p.mul(G::Scalar::char()).is_zero()
+}
+
+fn check_points<G: CurveAffine, R: Rng>(
+    query: &'static str,
+    points: &[G],
+    rng: &mut R
+) -> Result<(), ParameterError>
+{
+    if let Some(index) = points.iter().position(|p| p.is_zero()) {
+        return Err(ParameterError::InvalidPoint { query, index });
+    }
+
+    if points.is_empty() {
+        return Ok(());
+    }
+
+    for _ in 0..SUBGROUP_SAMPLES {
+        let index = rng.gen_range(0, points.len());
+        if !is_valid_point(&points[index]) {
+            return Err(ParameterError::InvalidPoint { query, index });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the `params` are generated for the `circuit` and are
+/// consistent. The sizes of all queries are checked against the shape
+/// of the circuit, all points are checked to be not at infinity and
+/// randomly sampled points are checked to be in the prime order subgroup.
+///
+/// The pairing checks use random linear combinations from `rng`:
+/// B query in G1 must match the one in G2, and for the variables that
+/// are not used in C the IC and L queries must match the A and B
+/// queries. Other relations involve secret generators or powers of tau
+/// and can not be checked without a transcript of the ceremony.
+///
+/// So these points are only checked for their number and their subgroup,
+/// and `Ok(())` does not mean that they are correct:
+/// - the whole H query, which holds the powers of tau times `Z(tau) / delta`;
+/// - the IC and L points of the variables that are used in C.
+///
+/// Parameters from an untrusted source should come with a transcript of
+/// the ceremony, that has to be verified separately.
+pub fn verify_parameters<E, C, R>(
+    params: &Parameters<E>,
+    circuit: C,
+    rng: &mut R
+) -> Result<(), ParameterError>
+    where E: Engine, C: Circuit<E>, R: Rng
+{
+    let mut assembly = ShapeAssembly {
+        num_inputs: 0,
+        num_aux: 0,
+        num_constraints: 0,
+        a_aux_density: DensityTracker::new(),
+        b_input_density: DensityTracker::new(),
+        b_aux_density: DensityTracker::new(),
+        c_input_density: DensityTracker::new(),
+        c_aux_density: DensityTracker::new()
+    };
+
+    ConstraintSystem::<E>::alloc_input(&mut assembly, || "", || Ok(E::Fr::one()))?;
+
+    circuit.synthesize(&mut assembly)?;
+
+    for i in 0..assembly.num_inputs {
+        ConstraintSystem::<E>::enforce(&mut assembly, || "",
+            |lc| lc + Variable(Index::Input(i)),
+            |lc| lc,
+            |lc| lc,
+        );
+    }
+
+    let domain = EvaluationDomain::<E, _>::from_coeffs(vec![Scalar::<E>(E::Fr::zero()); assembly.num_constraints])?;
+    let b_len = assembly.b_input_density.get_total_density() + assembly.b_aux_density.get_total_density();
+
+    for &(query, expected, got) in &[
+        ("IC", assembly.num_inputs, params.vk.ic.len()),
+        ("H", domain.as_ref().len() - 1, params.h.len()),
+        ("L", assembly.num_aux, params.l.len()),
+        ("A", assembly.num_inputs + assembly.a_aux_density.get_total_density(), params.a.len()),
+        ("B in G1", b_len, params.b_g1.len()),
+        ("B in G2", b_len, params.b_g2.len())
+    ] {
+        if expected != got {
+            return Err(ParameterError::ShapeMismatch { query, expected, got });
+        }
+    }
+
+    let vk = &params.vk;
+    for &(query, point) in &[
+        ("alpha in G1", &vk.alpha_g1),
+        ("beta in G1", &vk.beta_g1),
+        ("delta in G1", &vk.delta_g1)
+    ] {
+        if !is_valid_point(point) {
+            return Err(ParameterError::InvalidPoint { query, index: 0 });
+        }
+    }
+    for &(query, point) in &[
+        ("beta in G2", &vk.beta_g2),
+        ("gamma in G2", &vk.gamma_g2),
+        ("delta in G2", &vk.delta_g2)
+    ] {
+        if !is_valid_point(point) {
+            return Err(ParameterError::InvalidPoint { query, index: 0 });
+        }
+    }
+
+    check_points("IC", &vk.ic, rng)?;
+    check_points("H", &params.h, rng)?;
+    check_points("L", &params.l, rng)?;
+    check_points("A", &params.a, rng)?;
+    check_points("B in G1", &params.b_g1, rng)?;
+    check_points("B in G2", &params.b_g2, rng)?;
+
+    // beta * delta = delta * beta
+    if E::pairing(vk.beta_g1, vk.delta_g2) != E::pairing(vk.delta_g1, vk.beta_g2) {
+        return Err(ParameterError::InconsistentPairing("beta and delta"));
+    }
+
+    let worker = Worker::global();
+
+    // v_i(tau) * delta in G1 and G2
+    {
+        let r = (0..b_len).map(|_| E::Fr::rand(rng).into_repr()).collect::<Vec<_>>();
+        let b_g1 = dense_multiexp(worker, &params.b_g1, &r)?.into_affine();
+        let b_g2 = dense_multiexp(worker, &params.b_g2, &r)?.into_affine();
+
+        if E::pairing(b_g1, vk.delta_g2) != E::pairing(vk.delta_g1, b_g2) {
+            return Err(ParameterError::InconsistentPairing("B queries in G1 and G2"));
+        }
+    }
+
+    // For a variable that is not used in C its element of the IC (or L) query
+    // is (beta * u_i(tau) + alpha * v_i(tau)) / gamma (or delta), so
+    // e(ic_i, gamma) = e(a_i, beta) * e(alpha, b_i)
+    let mut ic = vec![];
+    let mut l = vec![];
+    let mut a = vec![];
+    let mut b = vec![];
+    let mut ic_r = vec![];
+    let mut l_r = vec![];
+    let mut a_r = vec![];
+    let mut b_r = vec![];
+
+    let mut b_index = 0;
+    let b_inputs = (&assembly.b_input_density).iter();
+    let c_inputs = (&assembly.c_input_density).iter();
+    for (i, (in_b, in_c)) in b_inputs.zip(c_inputs).enumerate() {
+        if !in_c {
+            let r = E::Fr::rand(rng).into_repr();
+            ic.push(vk.ic[i]);
+            ic_r.push(r);
+            a.push(params.a[i]);
+            a_r.push(r);
+            if in_b {
+                b.push(params.b_g2[b_index]);
+                b_r.push(r);
+            }
+        }
+        if in_b {
+            b_index += 1;
+        }
+    }
+
+    let mut a_index = assembly.num_inputs;
+    let a_aux = (&assembly.a_aux_density).iter();
+    let b_aux = (&assembly.b_aux_density).iter();
+    let c_aux = (&assembly.c_aux_density).iter();
+    for (i, ((in_a, in_b), in_c)) in a_aux.zip(b_aux).zip(c_aux).enumerate() {
+        if !in_c {
+            let r = E::Fr::rand(rng).into_repr();
+            l.push(params.l[i]);
+            l_r.push(r);
+            if in_a {
+                a.push(params.a[a_index]);
+                a_r.push(r);
+            }
+            if in_b {
+                b.push(params.b_g2[b_index]);
+                b_r.push(r);
+            }
+        }
+        if in_a {
+            a_index += 1;
+        }
+        if in_b {
+            b_index += 1;
+        }
+    }
+
+    if !ic.is_empty() || !l.is_empty() {
+        let mut a = dense_multiexp(worker, &a, &a_r)?;
+        a.negate();
+        let mut b = dense_multiexp(worker, &b, &b_r)?;
+        b.negate();
+
+        let mut prepared = vec![
+            (a.into_affine().prepare(), vk.beta_g2.prepare()),
+            (vk.alpha_g1.prepare(), b.into_affine().prepare())
+        ];
+        if !ic.is_empty() {
+            let ic = dense_multiexp(worker, &ic, &ic_r)?.into_affine();
+            prepared.push((ic.prepare(), vk.gamma_g2.prepare()));
+        }
+        if !l.is_empty() {
+            let l = dense_multiexp(worker, &l, &l_r)?.into_affine();
+            prepared.push((l.prepare(), vk.delta_g2.prepare()));
+        }
+        let pairs = prepared.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+
+        if E::final_exponentiation(&E::miller_loop(pairs.iter())) != Some(E::Fqk::one()) {
+            return Err(ParameterError::InconsistentPairing("IC and L queries"));
+        }
+    }
+
+    Ok(())
+}
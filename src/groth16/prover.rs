@@ -2,7 +2,12 @@ use crate::log::Stopwatch;
 
 use rand::Rng;
 
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
 use std::sync::Arc;
+use std::time::Duration;
+
+use byteorder::{BigEndian, WriteBytesExt, ReadBytesExt};
 
 use futures::Future;
 
@@ -14,12 +19,17 @@ use crate::pairing::{
 
 use crate::pairing::ff::{
     PrimeField,
+    PrimeFieldRepr,
     Field
 };
 
 use super::{
     ParameterSource,
-    Proof
+    Parameters,
+    Proof,
+    VerifyingKey,
+    prepare_verifying_key,
+    verify_proof
 };
 
 use crate::{
@@ -40,11 +50,29 @@ use crate::domain::{
 
 use crate::source::{
     DensityTracker,
-    FullDensity
+    FullDensity,
+    QueryDensity,
+    Source,
+    SourceBuilder
 };
 
 use crate::multiexp::*;
 
+use crate::backend::{
+    ComputeBackend,
+    CpuBackend,
+    multiexp_g1_with_fallback,
+    multiexp_g2_with_fallback
+};
+
+use crate::observer::{
+    ProvingObserver,
+    NullObserver,
+    PHASE_SYNTHESIS,
+    PHASE_FFT,
+    PHASE_MULTIEXP
+};
+
 use crate::worker::{
     Worker
 };
@@ -131,6 +159,98 @@ impl<E: Engine> ProvingAssignment<E> {
             all_constraints: vec![]
         }
     }
+
+    /// Writes everything that is needed to create a proof. The constraints
+    /// themselves are not needed for it, so they are not written.
+    pub fn write<W: Write>(
+        &self,
+        mut writer: W
+    ) -> io::Result<()>
+    {
+        self.a_aux_density.write(&mut writer)?;
+        self.b_input_density.write(&mut writer)?;
+        self.b_aux_density.write(&mut writer)?;
+
+        fn write_len<W: Write>(writer: &mut W, len: usize) -> io::Result<()> {
+            let len = u32::try_from(len).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "too many elements to serialize")
+            })?;
+
+            writer.write_u32::<BigEndian>(len)
+        }
+
+        for evaluations in &[&self.a, &self.b, &self.c] {
+            write_len(&mut writer, evaluations.len())?;
+            for e in evaluations.iter() {
+                e.0.into_repr().write_be(&mut writer)?;
+            }
+        }
+
+        for assignment in &[&self.input_assignment, &self.aux_assignment] {
+            write_len(&mut writer, assignment.len())?;
+            for e in assignment.iter() {
+                e.into_repr().write_be(&mut writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads an assignment written by `write`. The lengths of the evaluations
+    /// and of the density maps are checked against each other, so the
+    /// assignment can be proven without panicking.
+    pub fn read<R: Read>(
+        mut reader: R
+    ) -> io::Result<Self>
+    {
+        fn read_elements<E: Engine, R: Read>(reader: &mut R) -> io::Result<Vec<E::Fr>> {
+            let len = reader.read_u32::<BigEndian>()? as usize;
+            // the length is not trusted before the elements are actually there
+            let mut elements = Vec::with_capacity(std::cmp::min(len, 1 << 16));
+            let mut repr = <E::Fr as PrimeField>::Repr::default();
+            for _ in 0..len {
+                repr.read_be(&mut *reader)?;
+                let e = E::Fr::from_repr(repr).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                elements.push(e);
+            }
+
+            Ok(elements)
+        }
+
+        let a_aux_density = DensityTracker::read(&mut reader)?;
+        let b_input_density = DensityTracker::read(&mut reader)?;
+        let b_aux_density = DensityTracker::read(&mut reader)?;
+
+        let a = read_elements::<E, _>(&mut reader)?.into_iter().map(Scalar).collect::<Vec<_>>();
+        let b = read_elements::<E, _>(&mut reader)?.into_iter().map(Scalar).collect::<Vec<_>>();
+        let c = read_elements::<E, _>(&mut reader)?.into_iter().map(Scalar).collect::<Vec<_>>();
+
+        let input_assignment = read_elements::<E, _>(&mut reader)?;
+        let aux_assignment = read_elements::<E, _>(&mut reader)?;
+
+        let invalid = |msg| Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        if a.len() != b.len() || a.len() != c.len() {
+            return invalid("evaluations of A, B and C have different lengths");
+        }
+        if a_aux_density.get_query_size() != Some(aux_assignment.len()) ||
+           b_aux_density.get_query_size() != Some(aux_assignment.len()) ||
+           b_input_density.get_query_size() != Some(input_assignment.len())
+        {
+            return invalid("density map does not match the assignment");
+        }
+
+        Ok(ProvingAssignment {
+            a_aux_density,
+            b_input_density,
+            b_aux_density,
+            a,
+            b,
+            c,
+            input_assignment,
+            aux_assignment,
+            all_constraints: vec![]
+        })
+    }
 }
 
 impl<E: Engine> PartialEq for ProvingAssignment<E> {
@@ -336,10 +456,13 @@ impl<E: Engine> ThreadProvingAssignment<E> {
     }
 }
 
-pub fn prepare_prover<E, C>(
+/// Runs the witness generation of the `circuit`. The result contains everything
+/// that is needed to create a proof, so the proof can be created later, on
+/// another machine or several times with different blinding.
+pub fn synthesize_circuit<E, C>(
     circuit: C,
-) -> Result<PreparedProver<E>, SynthesisError>
-    where E: Engine, C: Circuit<E> 
+) -> Result<ProvingAssignment<E>, SynthesisError>
+    where E: Engine, C: Circuit<E>
 {
     let mut prover = ProvingAssignment {
         a_aux_density: DensityTracker::new(),
@@ -365,168 +488,553 @@ pub fn prepare_prover<E, C>(
         );
     }
 
+    Ok(prover)
+}
+
+pub fn prepare_prover<E, C>(
+    circuit: C,
+) -> Result<PreparedProver<E>, SynthesisError>
+    where E: Engine, C: Circuit<E> 
+{
     let prepared = PreparedProver {
-        assignment: prover
+        assignment: synthesize_circuit(circuit)?
     };
 
     return Ok(prepared)
 }
 
-impl<E:Engine> PreparedProver<E> {
-    pub fn create_random_proof<R, P: ParameterSource<E>>(
-        & self,
-        params: P,
-        rng: &mut R
-    ) -> Result<Proof<E>, SynthesisError>
-        where R: Rng
+/// Constraints of a circuit in a compact form together with the densities
+/// of the queries. Circuits whose witness changes, but whose constraints
+/// stay the same, can be synthesized with `synthesize_witness`, which
+/// only runs the allocations and never builds a linear combination.
+#[derive(Clone)]
+pub struct CachedShape<E: Engine> {
+    num_inputs: usize,
+    num_aux: usize,
+
+    a_aux_density: DensityTracker,
+    b_input_density: DensityTracker,
+    b_aux_density: DensityTracker,
+
+    // A, B and C of all constraints one after another
+    terms: Vec<(Variable, E::Fr)>,
+    // end of A, B and C of every constraint in `terms`
+    ends: Vec<usize>
+}
+
+impl<E: Engine> CachedShape<E> {
+    /// Records the constraints of the `circuit`. The values of the variables
+    /// are not computed, so the circuit does not need a witness.
+    pub fn from_circuit<C: Circuit<E>>(
+        circuit: C
+    ) -> Result<Self, SynthesisError>
     {
-        let r = rng.gen();
-        let s = rng.gen();
+        let mut recorder = ShapeRecorder(CachedShape {
+            num_inputs: 0,
+            num_aux: 0,
+            a_aux_density: DensityTracker::new(),
+            b_input_density: DensityTracker::new(),
+            b_aux_density: DensityTracker::new(),
+            terms: vec![],
+            ends: vec![]
+        });
 
-        self.create_proof(params, r, s)
+        recorder.alloc_input(|| "", || Ok(E::Fr::one()))?;
+
+        circuit.synthesize(&mut recorder)?;
+
+        for i in 0..recorder.0.num_inputs {
+            recorder.enforce(|| "",
+                |lc| lc + Variable(Index::Input(i)),
+                |lc| lc,
+                |lc| lc,
+            );
+        }
+
+        let mut shape = recorder.0;
+        shape.build_density_indices();
+
+        Ok(shape)
     }
 
-    pub fn create_proof<P: ParameterSource<E>>(
-        & self,
-        mut params: P,
-        r: E::Fr,
-        s: E::Fr
-    ) -> Result<Proof<E>, SynthesisError>
+    pub fn num_constraints(&self) -> usize {
+        self.ends.len() / 3
+    }
+
+    fn build_density_indices(&mut self) {
+        self.a_aux_density.build_index();
+        self.b_input_density.build_index();
+        self.b_aux_density.build_index();
+    }
+
+    /// Writes the shape, so it can be stored next to the parameters and
+    /// the circuit does not need to be recorded again.
+    pub fn write<W: Write>(
+        &self,
+        mut writer: W
+    ) -> io::Result<()>
     {
-        let prover = self.assignment.clone();
-        let worker = Worker::new();
-
-        let vk = params.get_vk(self.assignment.input_assignment.len())?;
-
-        let stopwatch = Stopwatch::new();
-
-        let h = {
-            let mut a = EvaluationDomain::from_coeffs(prover.a)?;
-            let mut b = EvaluationDomain::from_coeffs(prover.b)?;
-            let mut c = EvaluationDomain::from_coeffs(prover.c)?;
-            elog_verbose!("H query domain size is {}", a.as_ref().len());
-
-            // here a coset is a domain where denominator (z) does not vanish
-            // inverse FFT is an interpolation
-            a.ifft(&worker);
-            // evaluate in coset
-            a.coset_fft(&worker);
-            // same is for B and C
-            b.ifft(&worker);
-            b.coset_fft(&worker);
-            c.ifft(&worker);
-            c.coset_fft(&worker);
-
-            // do A*B-C in coset
-            a.mul_assign(&worker, &b);
-            drop(b);
-            a.sub_assign(&worker, &c);
-            drop(c);
-            // z does not vanish in coset, so we divide by non-zero
-            a.divide_by_z_on_coset(&worker);
-            // interpolate back in coset
-            a.icoset_fft(&worker);
-            let mut a = a.into_coeffs();
-            let a_len = a.len() - 1;
-            a.truncate(a_len);
-            // TODO: parallelize if it's even helpful
-            // TODO: in large settings it may worth to parallelize
-            let a = Arc::new(a.into_iter().map(|s| s.0.into_repr()).collect::<Vec<_>>());
-
-            multiexp(&worker, params.get_h(a.len())?, FullDensity, a)
-        };
+        self.a_aux_density.write(&mut writer)?;
+        self.b_input_density.write(&mut writer)?;
+        self.b_aux_density.write(&mut writer)?;
 
-        elog_verbose!("{} seconds for prover for H evaluation (mostly FFT)", stopwatch.elapsed());
+        fn write_usize<W: Write>(writer: &mut W, value: usize) -> io::Result<()> {
+            let value = u32::try_from(value).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "shape is too large to serialize")
+            })?;
 
-        let stopwatch = Stopwatch::new();
+            writer.write_u32::<BigEndian>(value)
+        }
 
-        // TODO: Check that difference in operations for different chunks is small
+        write_usize(&mut writer, self.num_inputs)?;
+        write_usize(&mut writer, self.num_aux)?;
+
+        write_usize(&mut writer, self.terms.len())?;
+        for &(var, ref coeff) in &self.terms {
+            match var.get_unchecked() {
+                Index::Input(i) => {
+                    writer.write_u8(0)?;
+                    write_usize(&mut writer, i)?;
+                },
+                Index::Aux(i) => {
+                    writer.write_u8(1)?;
+                    write_usize(&mut writer, i)?;
+                }
+            }
+            coeff.into_repr().write_be(&mut writer)?;
+        }
 
-        // TODO: parallelize if it's even helpful
-        // TODO: in large settings it may worth to parallelize
-        let input_assignment = Arc::new(prover.input_assignment.into_iter().map(|s| s.into_repr()).collect::<Vec<_>>());
-        let aux_assignment = Arc::new(prover.aux_assignment.into_iter().map(|s| s.into_repr()).collect::<Vec<_>>());
+        write_usize(&mut writer, self.ends.len())?;
+        for &end in &self.ends {
+            write_usize(&mut writer, end)?;
+        }
 
-        let input_len = input_assignment.len();
-        let aux_len = aux_assignment.len();
-        elog_verbose!("H query is dense in G1,\nOther queries are {} elements in G1 and {} elements in G2",
-            2*(input_len + aux_len) + aux_len, input_len + aux_len);
+        Ok(())
+    }
 
-        // Run a dedicated process for dense vector
-        let l = multiexp(&worker, params.get_l(aux_assignment.len())?, FullDensity, aux_assignment.clone());
+    pub fn read<R: Read>(
+        mut reader: R
+    ) -> io::Result<Self>
+    {
+        fn invalid(message: &'static str) -> io::Error {
+            io::Error::new(io::ErrorKind::InvalidData, message)
+        }
 
-        let a_aux_density_total = prover.a_aux_density.get_total_density();
+        let a_aux_density = DensityTracker::read(&mut reader)?;
+        let b_input_density = DensityTracker::read(&mut reader)?;
+        let b_aux_density = DensityTracker::read(&mut reader)?;
 
-        let (a_inputs_source, a_aux_source) = params.get_a(input_assignment.len(), a_aux_density_total)?;
+        let num_inputs = reader.read_u32::<BigEndian>()? as usize;
+        let num_aux = reader.read_u32::<BigEndian>()? as usize;
 
-        let a_inputs = multiexp(&worker, a_inputs_source, FullDensity, input_assignment.clone());
-        let a_aux = multiexp(&worker, a_aux_source, Arc::new(prover.a_aux_density), aux_assignment.clone());
+        if b_input_density.get_query_size() != Some(num_inputs)
+            || a_aux_density.get_query_size() != Some(num_aux)
+            || b_aux_density.get_query_size() != Some(num_aux)
+        {
+            return Err(invalid("densities do not match the number of variables"));
+        }
 
-        let b_input_density = Arc::new(prover.b_input_density);
-        let b_input_density_total = b_input_density.get_total_density();
-        let b_aux_density = Arc::new(prover.b_aux_density);
-        let b_aux_density_total = b_aux_density.get_total_density();
+        // the lengths are not trusted before the terms and the ends are actually there
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let mut terms = Vec::with_capacity(std::cmp::min(len, 1 << 16));
+        let mut repr = <E::Fr as PrimeField>::Repr::default();
+        for _ in 0..len {
+            let tag = reader.read_u8()?;
+            let i = reader.read_u32::<BigEndian>()? as usize;
+            let var = match tag {
+                0 if i < num_inputs => Variable(Index::Input(i)),
+                1 if i < num_aux => Variable(Index::Aux(i)),
+                0 | 1 => return Err(invalid("variable out of range")),
+                _ => return Err(invalid("unknown variable type"))
+            };
+
+            repr.read_be(&mut reader)?;
+            let coeff = E::Fr::from_repr(repr).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            terms.push((var, coeff));
+        }
 
-        let (b_g1_inputs_source, b_g1_aux_source) = params.get_b_g1(b_input_density_total, b_aux_density_total)?;
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        if !len.is_multiple_of(3) {
+            return Err(invalid("every constraint has A, B and C"));
+        }
+        let mut ends = Vec::with_capacity(std::cmp::min(len, 1 << 16));
+        let mut start = 0;
+        for _ in 0..len {
+            let end = reader.read_u32::<BigEndian>()? as usize;
+            if end < start || end > terms.len() {
+                return Err(invalid("linear combination out of range"));
+            }
+            ends.push(end);
+            start = end;
+        }
 
-        let b_g1_inputs = multiexp(&worker, b_g1_inputs_source, b_input_density.clone(), input_assignment.clone());
-        let b_g1_aux = multiexp(&worker, b_g1_aux_source, b_aux_density.clone(), aux_assignment.clone());
+        // the densities are indexed by `DensityTracker::read`
+        Ok(CachedShape {
+            num_inputs,
+            num_aux,
+            a_aux_density,
+            b_input_density,
+            b_aux_density,
+            terms,
+            ends
+        })
+    }
+}
 
-        let (b_g2_inputs_source, b_g2_aux_source) = params.get_b_g2(b_input_density_total, b_aux_density_total)?;
-        
-        let b_g2_inputs = multiexp(&worker, b_g2_inputs_source, b_input_density, input_assignment);
-        let b_g2_aux = multiexp(&worker, b_g2_aux_source, b_aux_density, aux_assignment);
-
-        if vk.delta_g1.is_zero() || vk.delta_g2.is_zero() {
-            // If this element is zero, someone is trying to perform a
-            // subversion-CRS attack.
-            return Err(SynthesisError::UnexpectedIdentity);
+struct ShapeRecorder<E: Engine>(CachedShape<E>);
+
+impl<E: Engine> ConstraintSystem<E> for ShapeRecorder<E> {
+    type Root = Self;
+
+    const WANTS_ANNOTATIONS: bool = false;
+
+    fn alloc<F, A, AR>(
+        &mut self,
+        _: A,
+        _: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        self.0.num_aux += 1;
+        self.0.a_aux_density.add_element();
+        self.0.b_aux_density.add_element();
+
+        Ok(Variable(Index::Aux(self.0.num_aux - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(
+        &mut self,
+        _: A,
+        _: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        self.0.num_inputs += 1;
+        self.0.b_input_density.add_element();
+
+        Ok(Variable(Index::Input(self.0.num_inputs - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(
+        &mut self,
+        _: A,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where A: FnOnce() -> AR, AR: Into<String>,
+              LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        let a = a(LinearCombination::zero());
+        let b = b(LinearCombination::zero());
+        let c = c(LinearCombination::zero());
+
+        // same densities as `eval` in `ProvingAssignment::enforce`
+        for &(var, _) in a.as_ref() {
+            if let Index::Aux(i) = var.get_unchecked() {
+                self.0.a_aux_density.inc(i);
+            }
+        }
+        for &(var, _) in b.as_ref() {
+            match var.get_unchecked() {
+                Index::Input(i) => self.0.b_input_density.inc(i),
+                Index::Aux(i) => self.0.b_aux_density.inc(i)
+            }
         }
 
-        let mut g_a = vk.delta_g1.mul(r);
-        g_a.add_assign_mixed(&vk.alpha_g1);
-        let mut g_b = vk.delta_g2.mul(s);
-        g_b.add_assign_mixed(&vk.beta_g2);
-        let mut g_c;
-        {
-            let mut rs = r;
-            rs.mul_assign(&s);
+        for lc in [a, b, c] {
+            self.0.terms.extend(lc.0);
+            self.0.ends.push(self.0.terms.len());
+        }
+    }
 
-            g_c = vk.delta_g1.mul(rs);
-            g_c.add_assign(&vk.alpha_g1.mul(s));
-            g_c.add_assign(&vk.beta_g1.mul(r));
+    fn push_namespace<NR, N>(&mut self, _: N)
+        where NR: Into<String>, N: FnOnce() -> NR
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self)
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root { self }
+}
+
+/// Assignment of the variables only, used by `synthesize_witness`.
+struct WitnessAssignment<E: Engine> {
+    input_assignment: Vec<E::Fr>,
+    aux_assignment: Vec<E::Fr>
+}
+
+impl<E: Engine> ConstraintSystem<E> for WitnessAssignment<E> {
+    type Root = Self;
+
+    const WANTS_ANNOTATIONS: bool = false;
+
+    fn alloc<F, A, AR>(
+        &mut self,
+        _: A,
+        f: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        self.aux_assignment.push(f()?);
+
+        Ok(Variable(Index::Aux(self.aux_assignment.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(
+        &mut self,
+        _: A,
+        f: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        self.input_assignment.push(f()?);
+
+        Ok(Variable(Index::Input(self.input_assignment.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(
+        &mut self,
+        _: A,
+        _: LA,
+        _: LB,
+        _: LC
+    )
+        where A: FnOnce() -> AR, AR: Into<String>,
+              LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        // The constraints are taken from the cached shape.
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+        where NR: Into<String>, N: FnOnce() -> NR
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self)
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root { self }
+}
+
+/// Same as `synthesize_circuit`, but takes the constraints from the `shape`
+/// that was recorded for the same circuit, so `enforce` does nothing.
+/// Returns `SynthesisError::ShapeMismatch` if the circuit allocates a different
+/// number of variables than the shape.
+pub fn synthesize_witness<E, C>(
+    shape: &CachedShape<E>,
+    circuit: C
+) -> Result<ProvingAssignment<E>, SynthesisError>
+    where E: Engine, C: Circuit<E>
+{
+    let mut witness = WitnessAssignment {
+        input_assignment: Vec::with_capacity(shape.num_inputs),
+        aux_assignment: Vec::with_capacity(shape.num_aux)
+    };
+
+    witness.alloc_input(|| "", || Ok(E::Fr::one()))?;
+
+    circuit.synthesize(&mut witness)?;
+
+    if witness.input_assignment.len() != shape.num_inputs || witness.aux_assignment.len() != shape.num_aux {
+        return Err(SynthesisError::ShapeMismatch);
+    }
+
+    // the evaluations are padded to the size of the domain by the FFT
+    let domain_size = shape.num_constraints().next_power_of_two();
+    let mut evaluations: Vec<Vec<_>> = (0..3).map(|_| Vec::with_capacity(domain_size)).collect();
+    let mut start = 0;
+    for (i, &end) in shape.ends.iter().enumerate() {
+        let mut acc = E::Fr::zero();
+
+        for &(var, ref coeff) in &shape.terms[start..end] {
+            let mut tmp = match var.get_unchecked() {
+                Index::Input(index) => witness.input_assignment[index],
+                Index::Aux(index) => witness.aux_assignment[index]
+            };
+            tmp.mul_assign(coeff);
+            acc.add_assign(&tmp);
         }
-        let mut a_answer = a_inputs.wait()?;
-        a_answer.add_assign(&a_aux.wait()?);
-        g_a.add_assign(&a_answer);
-        a_answer.mul_assign(s);
-        g_c.add_assign(&a_answer);
-
-        let mut b1_answer = b_g1_inputs.wait()?;
-        b1_answer.add_assign(&b_g1_aux.wait()?);
-        let mut b2_answer = b_g2_inputs.wait()?;
-        b2_answer.add_assign(&b_g2_aux.wait()?);
-
-        g_b.add_assign(&b2_answer);
-        b1_answer.mul_assign(r);
-        g_c.add_assign(&b1_answer);
-        g_c.add_assign(&h.wait()?);
-        g_c.add_assign(&l.wait()?);
-
-        elog_verbose!("{} seconds for prover for point multiplication", stopwatch.elapsed());
-
-        Ok(Proof {
-            a: g_a.into_affine(),
-            b: g_b.into_affine(),
-            c: g_c.into_affine()
+
+        evaluations[i % 3].push(Scalar(acc));
+        start = end;
+    }
+
+    let c = evaluations.pop().expect("three evaluations");
+    let b = evaluations.pop().expect("three evaluations");
+    let a = evaluations.pop().expect("three evaluations");
+
+    Ok(ProvingAssignment {
+        a_aux_density: shape.a_aux_density.clone(),
+        b_input_density: shape.b_input_density.clone(),
+        b_aux_density: shape.b_aux_density.clone(),
+        a,
+        b,
+        c,
+        input_assignment: witness.input_assignment,
+        aux_assignment: witness.aux_assignment,
+        all_constraints: vec![]
+    })
+}
+
+/// Proves many instances of the same circuit. The constraints are recorded
+/// once into a `CachedShape`, so every proof only computes the witness
+/// instead of building the linear combinations again, and all the proofs run
+/// on the same worker pool. Nothing else is kept between the proofs: the FFTs
+/// compute their roots of unity and the prover allocates its buffers anew for
+/// every proof, as `create_proof` does.
+pub struct ProvingSession<E: Engine> {
+    params: Parameters<E>,
+    shape: CachedShape<E>,
+    worker: Worker
+}
+
+impl<E: Engine> ProvingSession<E> {
+    /// Records the shape of the `circuit`, which does not need a witness.
+    /// The proofs run on `Worker::global()`.
+    pub fn new<C: Circuit<E>>(
+        params: Parameters<E>,
+        circuit: C
+    ) -> Result<Self, SynthesisError>
+    {
+        Self::with_worker(params, circuit, Worker::global().clone())
+    }
+
+    pub fn with_worker<C: Circuit<E>>(
+        params: Parameters<E>,
+        circuit: C,
+        worker: Worker
+    ) -> Result<Self, SynthesisError>
+    {
+        Ok(ProvingSession {
+            params,
+            shape: CachedShape::from_circuit(circuit)?,
+            worker
         })
     }
+
+    pub fn params(&self) -> &Parameters<E> {
+        &self.params
+    }
+
+    pub fn shape(&self) -> &CachedShape<E> {
+        &self.shape
+    }
+
+    pub fn prove<C, R>(
+        &self,
+        circuit: C,
+        rng: &mut R
+    ) -> Result<Proof<E>, SynthesisError>
+        where C: Circuit<E>, R: Rng
+    {
+        let r = rng.gen();
+        let s = rng.gen();
+
+        self.create_proof(circuit, r, s)
+    }
+
+    /// Same as `create_proof` for the parameters of the session. Fails with
+    /// `SynthesisError::ShapeMismatch` if the circuit does not match the
+    /// recorded shape.
+    pub fn create_proof<C: Circuit<E>>(
+        &self,
+        circuit: C,
+        r: E::Fr,
+        s: E::Fr
+    ) -> Result<Proof<E>, SynthesisError>
+    {
+        let assignment = synthesize_witness(&self.shape, circuit)?;
+
+        create_proof_for_assignment(assignment, &self.params, r, s, &CpuBackend, &self.worker, &NullObserver)
+    }
+}
+
+impl<E:Engine> PreparedProver<E> {
+    pub fn create_random_proof<R, P: ParameterSource<E>>(
+        & self,
+        params: P,
+        rng: &mut R
+    ) -> Result<Proof<E>, SynthesisError>
+        where R: Rng
+    {
+        let r = rng.gen();
+        let s = rng.gen();
+
+        self.create_proof(params, r, s)
+    }
+
+    /// Same as `create_random_proof`, but runs on the given `worker`.
+    pub fn create_random_proof_with_worker<R, P: ParameterSource<E>>(
+        & self,
+        params: P,
+        rng: &mut R,
+        worker: &Worker
+    ) -> Result<Proof<E>, SynthesisError>
+        where R: Rng
+    {
+        let r = rng.gen();
+        let s = rng.gen();
+
+        self.create_proof_with_worker(params, r, s, worker)
+    }
+
+    pub fn create_proof<P: ParameterSource<E>>(
+        & self,
+        params: P,
+        r: E::Fr,
+        s: E::Fr
+    ) -> Result<Proof<E>, SynthesisError>
+    {
+        self.create_proof_with_worker(params, r, s, Worker::global())
+    }
+
+    /// Same as `create_proof`, but runs on the given `worker`, e.g. a pool
+    /// shared with the rest of the application.
+    pub fn create_proof_with_worker<P: ParameterSource<E>>(
+        & self,
+        params: P,
+        r: E::Fr,
+        s: E::Fr,
+        worker: &Worker
+    ) -> Result<Proof<E>, SynthesisError>
+    {
+        self.create_proof_with_observer(params, r, s, worker, &NullObserver)
+    }
+
+    /// Same as `create_proof_with_worker`, but reports the phases
+    /// of the prover to the `observer`.
+    pub fn create_proof_with_observer<P: ParameterSource<E>, O: ProvingObserver + ?Sized>(
+        & self,
+        params: P,
+        r: E::Fr,
+        s: E::Fr,
+        worker: &Worker,
+        observer: &O
+    ) -> Result<Proof<E>, SynthesisError>
+    {
+        create_proof_for_assignment(self.assignment.clone(), params, r, s, &CpuBackend, worker, observer)
+    }
 }
 
 
 impl<E: Engine> ConstraintSystem<E> for ProvingAssignment<E> {
     type Root = Self;
 
+    const WANTS_ANNOTATIONS: bool = false;
+
     fn alloc<F, A, AR>(
         &mut self,
         _: A,
@@ -631,6 +1139,8 @@ impl<E: Engine> ConstraintSystem<E> for ProvingAssignment<E> {
 impl<E: Engine> ConstraintSystem<E> for ThreadProvingAssignment<E> {
     type Root = Self;
 
+    const WANTS_ANNOTATIONS: bool = false;
+
     fn alloc<F, A, AR>(
         &mut self,
         _: A,
@@ -721,118 +1231,236 @@ pub fn create_random_proof<E, C, R, P: ParameterSource<E>>(
 
 pub fn create_proof<E, C, P: ParameterSource<E>>(
     circuit: C,
-    mut params: P,
+    params: P,
     r: E::Fr,
     s: E::Fr
 ) -> Result<Proof<E>, SynthesisError>
     where E: Engine, C: Circuit<E>
 {
-    let mut prover = ProvingAssignment {
-        a_aux_density: DensityTracker::new(),
-        b_input_density: DensityTracker::new(),
-        b_aux_density: DensityTracker::new(),
-        a: vec![],
-        b: vec![],
-        c: vec![],
-        input_assignment: vec![],
-        aux_assignment: vec![],
-        all_constraints: vec![]
-    };
+    create_proof_with_worker(circuit, params, r, s, Worker::global())
+}
 
-    prover.alloc_input(|| "", || Ok(E::Fr::one()))?;
+pub fn create_random_proof_with_worker<E, C, R, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    rng: &mut R,
+    worker: &Worker
+) -> Result<Proof<E>, SynthesisError>
+    where E: Engine, C: Circuit<E>, R: Rng
+{
+    let r = rng.gen();
+    let s = rng.gen();
 
-    circuit.synthesize(&mut prover)?;
+    create_proof_with_worker::<E, C, P>(circuit, params, r, s, worker)
+}
 
-    for i in 0..prover.input_assignment.len() {
-        prover.enforce(|| "",
-            |lc| lc + Variable(Index::Input(i)),
-            |lc| lc,
-            |lc| lc,
-        );
-    }
+/// Same as `create_proof`, but runs on the given `worker`, e.g. a pool
+/// shared with the rest of the application.
+pub fn create_proof_with_worker<E, C, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    r: E::Fr,
+    s: E::Fr,
+    worker: &Worker
+) -> Result<Proof<E>, SynthesisError>
+    where E: Engine, C: Circuit<E>
+{
+    create_proof_with_observer(circuit, params, r, s, worker, &NullObserver)
+}
 
-    let worker = Worker::new();
+/// Same as `create_proof_with_worker`, but reports the phases
+/// of the prover to the `observer`.
+pub fn create_proof_with_observer<E, C, P: ParameterSource<E>, O: ProvingObserver + ?Sized>(
+    circuit: C,
+    params: P,
+    r: E::Fr,
+    s: E::Fr,
+    worker: &Worker,
+    observer: &O
+) -> Result<Proof<E>, SynthesisError>
+    where E: Engine, C: Circuit<E>
+{
+    observer.phase_started(PHASE_SYNTHESIS);
+    let stopwatch = Stopwatch::new();
+
+    let prover = synthesize_circuit(circuit)?;
+
+    observer.phase_finished(PHASE_SYNTHESIS, Duration::from_secs_f64(stopwatch.elapsed()));
+
+    create_proof_for_assignment(prover, params, r, s, &CpuBackend, worker, observer)
+}
 
+/// Creates a proof for an `assignment` that was produced by `synthesize_circuit`.
+/// The proof is the same as the one of `create_proof` for the same `r` and `s`.
+pub fn create_proof_from_assignment<E, P: ParameterSource<E>>(
+    params: P,
+    assignment: &ProvingAssignment<E>,
+    r: E::Fr,
+    s: E::Fr
+) -> Result<Proof<E>, SynthesisError>
+    where E: Engine
+{
+    create_proof_for_assignment(assignment.clone(), params, r, s, &CpuBackend, Worker::global(), &NullObserver)
+}
+
+fn create_proof_for_assignment<E, P, B, O>(
+    prover: ProvingAssignment<E>,
+    mut params: P,
+    r: E::Fr,
+    s: E::Fr,
+    backend: &B,
+    worker: &Worker,
+    observer: &O
+) -> Result<Proof<E>, SynthesisError>
+    where E: Engine, P: ParameterSource<E>, B: ComputeBackend<E> + ?Sized, O: ProvingObserver + ?Sized
+{
     let vk = params.get_vk(prover.input_assignment.len())?;
 
+    if vk.delta_g1.is_zero() || vk.delta_g2.is_zero() {
+        // If this element is zero, someone is trying to perform a
+        // subversion-CRS attack.
+        return Err(SynthesisError::UnexpectedIdentity);
+    }
+
+    observer.phase_started(PHASE_FFT);
     let stopwatch = Stopwatch::new();
 
-    let h = {
-        let mut a = EvaluationDomain::from_coeffs(prover.a)?;
-        let mut b = EvaluationDomain::from_coeffs(prover.b)?;
-        let mut c = EvaluationDomain::from_coeffs(prover.c)?;
+    let ProvingAssignment {
+        a,
+        b,
+        c,
+        input_assignment,
+        aux_assignment,
+        mut a_aux_density,
+        mut b_input_density,
+        mut b_aux_density,
+        ..
+    } = prover;
+
+    let a = worker.observe_chunks(|done, total| observer.progress(PHASE_FFT, done, total), |worker| -> Result<_, SynthesisError> {
+        let mut a = EvaluationDomain::from_coeffs(a)?;
+        let mut b = EvaluationDomain::from_coeffs(b)?;
+        let mut c = EvaluationDomain::from_coeffs(c)?;
         elog_verbose!("H query domain size is {}", a.as_ref().len());
+
         // here a coset is a domain where denominator (z) does not vanish
         // inverse FFT is an interpolation
-        a.ifft(&worker);
+        a.ifft_with_backend(worker, backend)?;
         // evaluate in coset
-        a.coset_fft(&worker);
+        a.coset_fft_with_backend(worker, backend)?;
         // same is for B and C
-        b.ifft(&worker);
-        b.coset_fft(&worker);
-        c.ifft(&worker);
-        c.coset_fft(&worker);
+        b.ifft_with_backend(worker, backend)?;
+        b.coset_fft_with_backend(worker, backend)?;
+        c.ifft_with_backend(worker, backend)?;
+        c.coset_fft_with_backend(worker, backend)?;
 
         // do A*B-C in coset
-        a.mul_assign(&worker, &b);
+        a.mul_assign(worker, &b)?;
         drop(b);
-        a.sub_assign(&worker, &c);
+        a.sub_assign(worker, &c)?;
         drop(c);
         // z does not vanish in coset, so we divide by non-zero
-        a.divide_by_z_on_coset(&worker);
+        a.divide_by_z_on_coset(worker)?;
         // interpolate back in coset
-        a.icoset_fft(&worker);
+        a.icoset_fft_with_backend(worker, backend)?;
         let mut a = a.into_coeffs();
         let a_len = a.len() - 1;
         a.truncate(a_len);
         // TODO: parallelize if it's even helpful
         // TODO: in large settings it may worth to parallelize
-        let a = Arc::new(a.into_iter().map(|s| s.0.into_repr()).collect::<Vec<_>>());
+        Ok(a.into_iter().map(|s| s.0.into_repr()).collect::<Vec<_>>())
+    })?;
 
-        multiexp(&worker, params.get_h(a.len())?, FullDensity, a)
-    };
-
-    elog_verbose!("{} seconds for prover for H evaluation (mostly FFT)", stopwatch.elapsed());
+    elog_verbose!("{} seconds for prover for H evaluation (mostly FFT) on {} backend", stopwatch.elapsed(), backend.name());
+    observer.phase_finished(PHASE_FFT, Duration::from_secs_f64(stopwatch.elapsed()));
 
+    observer.phase_started(PHASE_MULTIEXP);
     let stopwatch = Stopwatch::new();
 
-    // TODO: Check that difference in operations for different chunks is small
+    let (g1_answers, g2_answers) = worker.observe_chunks(|done, total| observer.progress(PHASE_MULTIEXP, done, total), |worker| -> Result<_, SynthesisError> {
+        // TODO: parallelize if it's even helpful
+        // TODO: in large settings it may worth to parallelize
+        let input_assignment = input_assignment.into_iter().map(|s| s.into_repr()).collect::<Vec<_>>();
+        let aux_assignment = aux_assignment.into_iter().map(|s| s.into_repr()).collect::<Vec<_>>();
 
-    // TODO: parallelize if it's even helpful
-    // TODO: in large settings it may worth to parallelize
-    let input_assignment = Arc::new(prover.input_assignment.into_iter().map(|s| s.into_repr()).collect::<Vec<_>>());
-    let aux_assignment = Arc::new(prover.aux_assignment.into_iter().map(|s| s.into_repr()).collect::<Vec<_>>());
+        let input_len = input_assignment.len();
+        let aux_len = aux_assignment.len();
+        elog_verbose!("H query is dense in G1,\nOther queries are {} elements in G1 and {} elements in G2",
+            2*(input_len + aux_len) + aux_len, input_len + aux_len);
 
-    // Run a dedicated process for dense vector
-    let l = multiexp(&worker, params.get_l(aux_assignment.len())?, FullDensity, aux_assignment.clone());
+        if !backend.is_cpu() {
+            // the backend only takes dense queries, so gather the bases
+            // and the exponents of the sparse ones
+            let h = params.get_h(a.len())?.new().read_bases(a.len())?;
+            let h = multiexp_g1_with_fallback(backend, worker, &h, &a)?;
+            let l = params.get_l(aux_len)?.new().read_bases(aux_len)?;
+            let l = multiexp_g1_with_fallback(backend, worker, &l, &aux_assignment)?;
+
+            let a_aux_exponents = filter_by_density(&aux_assignment, &a_aux_density);
+            let (a_inputs_source, a_aux_source) = params.get_a(input_len, a_aux_exponents.len())?;
+            let a_inputs = multiexp_g1_with_fallback(backend, worker, &a_inputs_source.new().read_bases(input_len)?, &input_assignment)?;
+            let a_aux = multiexp_g1_with_fallback(backend, worker, &a_aux_source.new().read_bases(a_aux_exponents.len())?, &a_aux_exponents)?;
+
+            let b_input_exponents = filter_by_density(&input_assignment, &b_input_density);
+            let b_aux_exponents = filter_by_density(&aux_assignment, &b_aux_density);
+            let b_input_len = b_input_exponents.len();
+            let b_aux_len = b_aux_exponents.len();
+
+            let (b_g1_inputs_source, b_g1_aux_source) = params.get_b_g1(b_input_len, b_aux_len)?;
+            let b_g1_inputs = multiexp_g1_with_fallback(backend, worker, &b_g1_inputs_source.new().read_bases(b_input_len)?, &b_input_exponents)?;
+            let b_g1_aux = multiexp_g1_with_fallback(backend, worker, &b_g1_aux_source.new().read_bases(b_aux_len)?, &b_aux_exponents)?;
+
+            let (b_g2_inputs_source, b_g2_aux_source) = params.get_b_g2(b_input_len, b_aux_len)?;
+            let b_g2_inputs = multiexp_g2_with_fallback(backend, worker, &b_g2_inputs_source.new().read_bases(b_input_len)?, &b_input_exponents)?;
+            let b_g2_aux = multiexp_g2_with_fallback(backend, worker, &b_g2_aux_source.new().read_bases(b_aux_len)?, &b_aux_exponents)?;
+
+            return Ok((vec![h, l, a_inputs, a_aux, b_g1_inputs, b_g1_aux], vec![b_g2_inputs, b_g2_aux]));
+        }
 
-    let a_aux_density_total = prover.a_aux_density.get_total_density();
+        let h = multiexp(worker, params.get_h(a.len())?, FullDensity, Arc::new(a));
 
-    let (a_inputs_source, a_aux_source) = params.get_a(input_assignment.len(), a_aux_density_total)?;
+        // TODO: Check that difference in operations for different chunks is small
 
-    let a_inputs = multiexp(&worker, a_inputs_source, FullDensity, input_assignment.clone());
-    let a_aux = multiexp(&worker, a_aux_source, Arc::new(prover.a_aux_density), aux_assignment.clone());
+        let input_assignment = Arc::new(input_assignment);
+        let aux_assignment = Arc::new(aux_assignment);
 
-    let b_input_density = Arc::new(prover.b_input_density);
-    let b_input_density_total = b_input_density.get_total_density();
-    let b_aux_density = Arc::new(prover.b_aux_density);
-    let b_aux_density_total = b_aux_density.get_total_density();
+        // Run a dedicated process for dense vector
+        let l = multiexp(worker, params.get_l(aux_len)?, FullDensity, aux_assignment.clone());
 
-    let (b_g1_inputs_source, b_g1_aux_source) = params.get_b_g1(b_input_density_total, b_aux_density_total)?;
+        // the multiexps split the sparse queries between the threads with `select`
+        a_aux_density.build_index();
+        b_input_density.build_index();
+        b_aux_density.build_index();
 
-    let b_g1_inputs = multiexp(&worker, b_g1_inputs_source, b_input_density.clone(), input_assignment.clone());
-    let b_g1_aux = multiexp(&worker, b_g1_aux_source, b_aux_density.clone(), aux_assignment.clone());
+        let a_aux_density_total = a_aux_density.get_total_density();
 
-    let (b_g2_inputs_source, b_g2_aux_source) = params.get_b_g2(b_input_density_total, b_aux_density_total)?;
+        let (a_inputs_source, a_aux_source) = params.get_a(input_len, a_aux_density_total)?;
+
+        let a_inputs = multiexp(worker, a_inputs_source, FullDensity, input_assignment.clone());
+        let a_aux = multiexp(worker, a_aux_source, Arc::new(a_aux_density), aux_assignment.clone());
+
+        let b_input_density = Arc::new(b_input_density);
+        let b_input_density_total = b_input_density.get_total_density();
+        let b_aux_density = Arc::new(b_aux_density);
+        let b_aux_density_total = b_aux_density.get_total_density();
+
+        let (b_g1_inputs_source, b_g1_aux_source) = params.get_b_g1(b_input_density_total, b_aux_density_total)?;
+
+        let b_g1_inputs = multiexp(worker, b_g1_inputs_source, b_input_density.clone(), input_assignment.clone());
+        let b_g1_aux = multiexp(worker, b_g1_aux_source, b_aux_density.clone(), aux_assignment.clone());
+
+        let (b_g2_inputs_source, b_g2_aux_source) = params.get_b_g2(b_input_density_total, b_aux_density_total)?;
     
-    let b_g2_inputs = multiexp(&worker, b_g2_inputs_source, b_input_density, input_assignment);
-    let b_g2_aux = multiexp(&worker, b_g2_aux_source, b_aux_density, aux_assignment);
+        let b_g2_inputs = multiexp(worker, b_g2_inputs_source, b_input_density, input_assignment);
+        let b_g2_aux = multiexp(worker, b_g2_aux_source, b_aux_density, aux_assignment);
 
-    if vk.delta_g1.is_zero() || vk.delta_g2.is_zero() {
-        // If this element is zero, someone is trying to perform a
-        // subversion-CRS attack.
-        return Err(SynthesisError::UnexpectedIdentity);
-    }
+        // Wait for all the multiexps at once, so the first failure
+        // drops (and cancels) the rest of them
+        let g1_answers = join_all(vec![h, l, a_inputs, a_aux, b_g1_inputs, b_g1_aux])?;
+        let g2_answers = join_all(vec![b_g2_inputs, b_g2_aux])?;
+
+        Ok((g1_answers, g2_answers))
+    })?;
 
     let mut g_a = vk.delta_g1.mul(r);
     g_a.add_assign_mixed(&vk.alpha_g1);
@@ -847,24 +1475,26 @@ pub fn create_proof<E, C, P: ParameterSource<E>>(
         g_c.add_assign(&vk.alpha_g1.mul(s));
         g_c.add_assign(&vk.beta_g1.mul(r));
     }
-    let mut a_answer = a_inputs.wait()?;
-    a_answer.add_assign(&a_aux.wait()?);
+
+    let mut a_answer = g1_answers[2];
+    a_answer.add_assign(&g1_answers[3]);
     g_a.add_assign(&a_answer);
     a_answer.mul_assign(s);
     g_c.add_assign(&a_answer);
 
-    let mut b1_answer = b_g1_inputs.wait()?;
-    b1_answer.add_assign(&b_g1_aux.wait()?);
-    let mut b2_answer = b_g2_inputs.wait()?;
-    b2_answer.add_assign(&b_g2_aux.wait()?);
+    let mut b1_answer = g1_answers[4];
+    b1_answer.add_assign(&g1_answers[5]);
+    let mut b2_answer = g2_answers[0];
+    b2_answer.add_assign(&g2_answers[1]);
 
     g_b.add_assign(&b2_answer);
     b1_answer.mul_assign(r);
     g_c.add_assign(&b1_answer);
-    g_c.add_assign(&h.wait()?);
-    g_c.add_assign(&l.wait()?);
+    g_c.add_assign(&g1_answers[0]);
+    g_c.add_assign(&g1_answers[1]);
 
-    elog_verbose!("{} seconds for prover for point multiplication", stopwatch.elapsed());
+    elog_verbose!("{} seconds for prover for point multiplication on {} backend", stopwatch.elapsed(), backend.name());
+    observer.phase_finished(PHASE_MULTIEXP, Duration::from_secs_f64(stopwatch.elapsed()));
 
     Ok(Proof {
         a: g_a.into_affine(),
@@ -872,3 +1502,127 @@ pub fn create_proof<E, C, P: ParameterSource<E>>(
         c: g_c.into_affine()
     })
 }
+
+/// Gathers the exponents for which the query has a base, so the query can
+/// be computed as a dense multiexp by an external backend.
+fn filter_by_density<R: Copy>(exponents: &[R], density: &DensityTracker) -> Vec<R> {
+    exponents.iter().zip(density.iter()).filter(|(_, d)| *d).map(|(e, _)| *e).collect()
+}
+
+impl<E: Engine> PreparedProver<E> {
+    /// Creates a proof routing all the multiexps and FFTs through the `backend`.
+    /// Kernels that fail on the backend are recomputed on the CPU. Unless the
+    /// backend is `CpuBackend`, the proof is verified before it is returned,
+    /// and if the backend has silently produced a wrong result the whole proof
+    /// is recomputed on the CPU. The CPU parts run on the `worker`.
+    pub fn create_proof_with_backend<P, B>(
+        &self,
+        params: P,
+        r: E::Fr,
+        s: E::Fr,
+        backend: &B,
+        worker: &Worker
+    ) -> Result<Proof<E>, SynthesisError>
+        where P: ParameterSource<E> + Clone, B: ComputeBackend<E> + ?Sized
+    {
+        let inputs = &self.assignment.input_assignment;
+        let vk = params.clone().get_vk(inputs.len())?;
+        if inputs.len() != vk.ic.len() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+
+        let proof = create_proof_for_assignment(self.assignment.clone(), params.clone(), r, s, backend, worker, &NullObserver)?;
+        if backend.is_cpu() {
+            return Ok(proof);
+        }
+
+        let pvk = prepare_verifying_key(&vk);
+        if verify_proof(&pvk, &proof, &inputs[1..])? {
+            return Ok(proof);
+        }
+
+        elog!("WARNING: proof created on {} backend is invalid, recomputing it on CPU", backend.name());
+
+        create_proof_for_assignment(self.assignment.clone(), params, r, s, &CpuBackend, worker, &NullObserver)
+    }
+
+    pub fn create_random_proof_with_backend<R, P, B>(
+        &self,
+        params: P,
+        rng: &mut R,
+        backend: &B,
+        worker: &Worker
+    ) -> Result<Proof<E>, SynthesisError>
+        where R: Rng, P: ParameterSource<E> + Clone, B: ComputeBackend<E> + ?Sized
+    {
+        let r = rng.gen();
+        let s = rng.gen();
+
+        self.create_proof_with_backend(params, r, s, backend, worker)
+    }
+}
+
+pub fn create_random_proof_with_backend<E, C, R, P, B>(
+    circuit: C,
+    params: P,
+    rng: &mut R,
+    backend: &B,
+    worker: &Worker
+) -> Result<Proof<E>, SynthesisError>
+    where E: Engine, C: Circuit<E>, R: Rng, P: ParameterSource<E> + Clone, B: ComputeBackend<E> + ?Sized
+{
+    let r = rng.gen();
+    let s = rng.gen();
+
+    create_proof_with_backend(circuit, params, r, s, backend, worker)
+}
+
+pub fn create_proof_with_backend<E, C, P, B>(
+    circuit: C,
+    params: P,
+    r: E::Fr,
+    s: E::Fr,
+    backend: &B,
+    worker: &Worker
+) -> Result<Proof<E>, SynthesisError>
+    where E: Engine, C: Circuit<E>, P: ParameterSource<E> + Clone, B: ComputeBackend<E> + ?Sized
+{
+    let prover = prepare_prover(circuit)?;
+
+    prover.create_proof_with_backend(params, r, s, backend, worker)
+}
+
+/// Re-randomizes a proof, so it can not be linked to the original one.
+/// Only the public `vk` is needed: for random `r1` and `r2`
+/// A' = A / r1, B' = r1 * B + r1 * r2 * delta, C' = C + r2 * A
+/// satisfy the same verification equation.
+pub fn rerandomize_proof<E: Engine, R: Rng>(
+    proof: &Proof<E>,
+    vk: &VerifyingKey<E>,
+    rng: &mut R
+) -> Proof<E>
+{
+    let r1 = loop {
+        let r1: E::Fr = rng.gen();
+        if !r1.is_zero() {
+            break r1;
+        }
+    };
+    let r2: E::Fr = rng.gen();
+
+    let g_a = proof.a.mul(r1.inverse().expect("r1 is not zero"));
+
+    let mut g_b = proof.b.mul(r1);
+    let mut r1r2 = r1;
+    r1r2.mul_assign(&r2);
+    g_b.add_assign(&vk.delta_g2.mul(r1r2));
+
+    let mut g_c = proof.c.into_projective();
+    g_c.add_assign(&proof.a.mul(r2));
+
+    Proof {
+        a: g_a.into_affine(),
+        b: g_b.into_affine(),
+        c: g_c.into_affine()
+    }
+}
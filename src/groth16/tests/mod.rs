@@ -8,7 +8,7 @@ use crate::pairing::ff:: {
 };
 
 use super::super::tests::dummy_engine::*;
-use super::super::tests::XORDemo;
+use super::super::tests::{XORDemo, xor_demo};
 
 use std::marker::PhantomData;
 
@@ -20,11 +20,24 @@ use crate::{
 
 use super::{
     generate_parameters,
+    generate_random_parameters,
     prepare_verifying_key,
     create_proof,
-    verify_proof
+    verify_proof,
+    Parameters
 };
 
+use rand::{XorShiftRng, SeedableRng};
+
+/// Parameters for `XORDemo` from a fixed seed, and the rng to draw
+/// everything else of a test from.
+fn xor_setup<E: Engine>() -> (Parameters<E>, XorShiftRng) {
+    let mut rng = XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+    let params = generate_random_parameters(xor_demo(None, None), &mut rng).unwrap();
+
+    (params, rng)
+}
+
 #[test]
 fn test_xordemo() {
     let g1 = Fr::one();
@@ -328,3 +341,1619 @@ fn test_xordemo() {
         &[Fr::one()]
     ).unwrap());
 }
+
+mod backend {
+    use crate::pairing::{Engine, CurveProjective};
+    use crate::pairing::ff::{Field, PrimeField};
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::backend::{ComputeBackend, CpuBackend};
+    use crate::domain::Scalar;
+    use crate::worker::Worker;
+    use crate::SynthesisError;
+    use crate::tests::xor_demo;
+
+    use super::xor_setup;
+    use super::super::{
+        prepare_prover,
+        prepare_verifying_key,
+        create_proof,
+        verify_proof,
+        IndexedParameters,
+        PointValidation,
+        Parameters
+    };
+
+    use rand::Rand;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Runs everything on the CPU, but counts the calls and can fail or
+    /// corrupt the result of the chosen G1 multiexp.
+    struct MockBackend {
+        fail_g1_call: Option<usize>,
+        corrupt_g1_call: Option<usize>,
+        fail_fft_call: Option<usize>,
+        g1_calls: AtomicUsize,
+        g2_calls: AtomicUsize,
+        fft_calls: AtomicUsize
+    }
+
+    impl MockBackend {
+        fn new(fail_g1_call: Option<usize>, corrupt_g1_call: Option<usize>, fail_fft_call: Option<usize>) -> Self {
+            MockBackend {
+                fail_g1_call,
+                corrupt_g1_call,
+                fail_fft_call,
+                g1_calls: AtomicUsize::new(0),
+                g2_calls: AtomicUsize::new(0),
+                fft_calls: AtomicUsize::new(0)
+            }
+        }
+    }
+
+    impl<E: Engine> ComputeBackend<E> for MockBackend {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        fn multiexp_g1(
+            &self,
+            worker: &Worker,
+            bases: &[E::G1Affine],
+            exponents: &[<E::Fr as PrimeField>::Repr]
+        ) -> Result<E::G1, SynthesisError>
+        {
+            let call = self.g1_calls.fetch_add(1, Ordering::SeqCst);
+            if self.fail_g1_call == Some(call) {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+
+            let mut result = ComputeBackend::<E>::multiexp_g1(&CpuBackend, worker, bases, exponents)?;
+            if self.corrupt_g1_call == Some(call) {
+                result.add_assign(&E::G1::one());
+            }
+
+            Ok(result)
+        }
+
+        fn multiexp_g2(
+            &self,
+            worker: &Worker,
+            bases: &[E::G2Affine],
+            exponents: &[<E::Fr as PrimeField>::Repr]
+        ) -> Result<E::G2, SynthesisError>
+        {
+            self.g2_calls.fetch_add(1, Ordering::SeqCst);
+
+            ComputeBackend::<E>::multiexp_g2(&CpuBackend, worker, bases, exponents)
+        }
+
+        fn fft_fr(
+            &self,
+            worker: &Worker,
+            a: &mut [Scalar<E>],
+            omega: &E::Fr,
+            log_n: u32
+        ) -> Result<(), SynthesisError>
+        {
+            let call = self.fft_calls.fetch_add(1, Ordering::SeqCst);
+            if self.fail_fft_call == Some(call) {
+                // leave some garbage behind
+                for v in a.iter_mut() {
+                    v.0 = E::Fr::one();
+                }
+
+                return Err(SynthesisError::Unsatisfiable);
+            }
+
+            CpuBackend.fft_fr(worker, a, omega, log_n)
+        }
+    }
+
+    fn setup() -> (Parameters<Bls12>, Fr, Fr) {
+        let (params, ref mut rng) = xor_setup::<Bls12>();
+
+        (params, Fr::rand(rng), Fr::rand(rng))
+    }
+
+    #[test]
+    fn test_cpu_backend_matches_prover() {
+        let (params, r, s) = setup();
+
+        let expected = create_proof(xor_demo(Some(true), Some(false)), &params, r, s).unwrap();
+        let proof = prepare_prover(xor_demo(Some(true), Some(false))).unwrap()
+            .create_proof_with_backend(&params, r, s, &CpuBackend, &Worker::new()).unwrap();
+
+        assert!(proof == expected);
+    }
+
+    #[test]
+    fn test_backend_failures_fall_back_to_cpu() {
+        let (params, r, s) = setup();
+
+        let expected = create_proof(xor_demo(Some(true), Some(true)), &params, r, s).unwrap();
+        let prover = prepare_prover(xor_demo(Some(true), Some(true))).unwrap();
+
+        let backend = MockBackend::new(Some(3), None, Some(0));
+        let proof = prover.create_proof_with_backend(&params, r, s, &backend, &Worker::new()).unwrap();
+
+        assert!(proof == expected);
+        // H, L, A for inputs and aux, B for inputs and aux
+        assert_eq!(backend.g1_calls.load(Ordering::SeqCst), 6);
+        assert_eq!(backend.g2_calls.load(Ordering::SeqCst), 2);
+        // two FFTs for each of A, B, C and one for H
+        assert_eq!(backend.fft_calls.load(Ordering::SeqCst), 7);
+    }
+
+    #[test]
+    fn test_corrupted_backend_result_is_recomputed() {
+        let (params, r, s) = setup();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let expected = create_proof(xor_demo(Some(false), Some(true)), &params, r, s).unwrap();
+        let prover = prepare_prover(xor_demo(Some(false), Some(true))).unwrap();
+
+        let backend = MockBackend::new(None, Some(1), None);
+        let proof = prover.create_proof_with_backend(&params, r, s, &backend, &Worker::new()).unwrap();
+
+        assert!(proof == expected);
+        assert!(verify_proof(&pvk, &proof, &[Fr::one()]).unwrap());
+        // the second attempt does not touch the backend
+        assert_eq!(backend.g1_calls.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn test_backend_reads_bases_from_parameter_source() {
+        let (params, r, s) = setup();
+
+        let mut encoding = vec![];
+        params.write_indexed(&mut encoding).unwrap();
+        let path = std::env::temp_dir().join(format!("bellman-{}-backend-params", std::process::id()));
+        std::fs::write(&path, &encoding).unwrap();
+
+        let expected = create_proof(xor_demo(Some(true), Some(false)), &params, r, s).unwrap();
+        let prover = prepare_prover(xor_demo(Some(true), Some(false))).unwrap();
+
+        let indexed = IndexedParameters::<Bls12>::open(&path, PointValidation::OnRead).unwrap();
+        let backend = MockBackend::new(None, None, None);
+        let proof = prover.create_proof_with_backend(&indexed, r, s, &backend, &Worker::new()).unwrap();
+
+        assert!(proof == expected);
+        assert_eq!(backend.g1_calls.load(Ordering::SeqCst), 6);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+mod observer {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::worker::Worker;
+    use crate::observer::ProvingObserver;
+    use crate::tests::xor_demo;
+
+    use super::xor_setup;
+    use super::super::{
+        create_proof,
+        create_proof_with_observer
+    };
+
+    use rand::Rand;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: Mutex<Vec<String>>
+    }
+
+    impl ProvingObserver for RecordingObserver {
+        fn phase_started(&self, name: &str) {
+            self.events.lock().unwrap().push(format!("start {}", name));
+        }
+
+        fn phase_finished(&self, name: &str, _duration: Duration) {
+            self.events.lock().unwrap().push(format!("finish {}", name));
+        }
+
+        fn progress(&self, name: &str, done: usize, total: usize) {
+            self.events.lock().unwrap().push(format!("{} {}/{}", name, done, total));
+        }
+    }
+
+    #[test]
+    fn test_observer_sees_all_phases() {
+        let (params, ref mut rng) = xor_setup::<Bls12>();
+        let r = Fr::rand(rng);
+        let s = Fr::rand(rng);
+
+        let observer = RecordingObserver::default();
+        let proof = create_proof_with_observer(xor_demo(Some(true), Some(false)), &params, r, s, &Worker::new_with_cpus(4), &observer).unwrap();
+        let expected = create_proof(xor_demo(Some(true), Some(false)), &params, r, s).unwrap();
+        assert!(proof == expected);
+
+        let events = observer.events.into_inner().unwrap();
+        let phases = events.iter().filter(|e| e.starts_with("start ") || e.starts_with("finish ")).collect::<Vec<_>>();
+        assert_eq!(phases, vec!["start synthesis", "finish synthesis", "start fft", "finish fft", "start multiexp", "finish multiexp"]);
+
+        // the chunks of the worker loops are reported in between, the last one completes the phase
+        for phase in &["fft", "multiexp"] {
+            let start = events.iter().position(|e| *e == format!("start {}", phase)).unwrap();
+            let finish = events.iter().position(|e| *e == format!("finish {}", phase)).unwrap();
+            let chunks = &events[(start + 1)..finish];
+            assert!(chunks.len() > 1);
+
+            for (i, event) in chunks.iter().enumerate() {
+                let prefix = format!("{} {}/", phase, i + 1);
+                assert!(event.starts_with(&prefix), "unexpected event {}", event);
+                let total: usize = event[prefix.len()..].parse().unwrap();
+                assert!(i < total);
+            }
+            assert_eq!(*chunks.last().unwrap(), format!("{} {}/{}", phase, chunks.len(), chunks.len()));
+        }
+    }
+}
+
+mod batch {
+    use crate::pairing::bn256::{Bn256, Fr};
+    use crate::pairing::ff::Field;
+    use crate::tests::xor_demo;
+    use crate::SynthesisError;
+
+    use super::xor_setup;
+    use super::super::{
+        create_random_proof,
+        prepare_verifying_key,
+        verify_proofs_batch,
+        PreparedVerifyingKey,
+        Proof
+    };
+
+    use rand::{XorShiftRng, SeedableRng, Rng};
+
+    fn setup(num_proofs: usize) -> (PreparedVerifyingKey<Bn256>, Vec<Proof<Bn256>>, Vec<Vec<Fr>>) {
+        let (params, ref mut rng) = xor_setup::<Bn256>();
+
+        let mut proofs = vec![];
+        let mut inputs = vec![];
+        for _ in 0..num_proofs {
+            let (a, b): (bool, bool) = (rng.gen(), rng.gen());
+            proofs.push(create_random_proof(xor_demo(Some(a), Some(b)), &params, rng).unwrap());
+            inputs.push(vec![if a ^ b { Fr::one() } else { Fr::zero() }]);
+        }
+
+        (prepare_verifying_key(&params.vk), proofs, inputs)
+    }
+
+    #[test]
+    fn test_batch_verification() {
+        let rng = &mut XorShiftRng::from_seed([0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let (pvk, mut proofs, mut inputs) = setup(50);
+
+        assert!(verify_proofs_batch(&pvk, &proofs, &inputs, rng).unwrap());
+        assert!(verify_proofs_batch(&pvk, &[], &[], rng).unwrap());
+
+        // flip the public input of one of the proofs
+        let mut wrong_inputs = inputs.clone();
+        wrong_inputs[17][0].add_assign(&Fr::one());
+        assert!(!verify_proofs_batch(&pvk, &proofs, &wrong_inputs, rng).unwrap());
+
+        // flip one byte of C in one of the proofs, until it is still a valid point
+        let mut encoding = vec![];
+        proofs[31].write(&mut encoding).unwrap();
+        let corrupted = (1..32).filter_map(|i| {
+            let mut encoding = encoding.clone();
+            let idx = encoding.len() - i;
+            encoding[idx] ^= 0x01;
+            Proof::<Bn256>::read(&encoding[..]).ok()
+        }).next().unwrap();
+        assert!(corrupted != proofs[31]);
+        proofs[31] = corrupted;
+        assert!(!verify_proofs_batch(&pvk, &proofs, &inputs, rng).unwrap());
+
+        proofs.pop();
+        match verify_proofs_batch(&pvk, &proofs, &inputs, rng) {
+            Err(SynthesisError::LengthMismatch { expected: 49, got: 50 }) => {},
+            _ => panic!("number of proofs and inputs does not match")
+        }
+
+        inputs.pop();
+        inputs[3].push(Fr::one());
+        match verify_proofs_batch(&pvk, &proofs, &inputs, rng) {
+            Err(SynthesisError::InputLengthMismatch { expected: 1, got: 2 }) => {},
+            _ => panic!("wrong number of public inputs")
+        }
+    }
+}
+
+mod rerandomization {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::Field;
+    use crate::tests::xor_demo;
+
+    use super::xor_setup;
+    use super::super::{
+        create_random_proof,
+        prepare_verifying_key,
+        rerandomize_proof,
+        verify_proof
+    };
+
+    #[test]
+    fn test_rerandomized_proof_verifies() {
+        let (params, ref mut rng) = xor_setup::<Bls12>();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let proof = create_random_proof(xor_demo(Some(true), Some(false)), &params, rng).unwrap();
+        assert!(verify_proof(&pvk, &proof, &[Fr::one()]).unwrap());
+
+        let rerandomized = rerandomize_proof(&proof, &params.vk, rng);
+        assert!(rerandomized.a != proof.a);
+        assert!(rerandomized.b != proof.b);
+        assert!(rerandomized.c != proof.c);
+        assert!(verify_proof(&pvk, &rerandomized, &[Fr::one()]).unwrap());
+        assert!(!verify_proof(&pvk, &rerandomized, &[Fr::zero()]).unwrap());
+
+        let twice = rerandomize_proof(&rerandomized, &params.vk, rng);
+        assert!(twice != rerandomized);
+        assert!(verify_proof(&pvk, &twice, &[Fr::one()]).unwrap());
+    }
+}
+
+mod determinism {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::Field;
+    use crate::tests::xor_demo;
+    use crate::testing::DeterministicRng;
+
+    use super::super::{
+        generate_random_parameters,
+        create_random_proof,
+        prepare_verifying_key,
+        verify_proof,
+        Parameters,
+        Proof
+    };
+
+    fn prove(params: &Parameters<Bls12>, seed: u64) -> (Proof<Bls12>, Vec<u8>) {
+        let proof = create_random_proof(xor_demo(Some(false), Some(true)), params, &mut DeterministicRng::from_seed(seed)).unwrap();
+        let mut encoding = vec![];
+        proof.write(&mut encoding).unwrap();
+
+        (proof, encoding)
+    }
+
+    #[test]
+    fn test_seeded_proofs_are_reproducible() {
+        let params = generate_random_parameters(xor_demo(None, None), &mut DeterministicRng::from_seed(1)).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let (proof_a, encoding_a) = prove(&params, 7);
+        let (_, encoding_b) = prove(&params, 7);
+        let (proof_c, encoding_c) = prove(&params, 8);
+
+        assert_eq!(encoding_a, encoding_b);
+        assert!(encoding_a != encoding_c);
+
+        assert!(verify_proof(&pvk, &proof_a, &[Fr::one()]).unwrap());
+        assert!(verify_proof(&pvk, &proof_c, &[Fr::one()]).unwrap());
+    }
+}
+
+mod assignment {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::Field;
+    use crate::tests::xor_demo;
+
+    use super::xor_setup;
+    use super::super::{
+        create_proof,
+        create_proof_from_assignment,
+        synthesize_circuit,
+        prepare_verifying_key,
+        verify_proof,
+        ProvingAssignment
+    };
+
+    use rand::Rand;
+    use std::io;
+
+    #[test]
+    fn test_proof_from_serialized_assignment() {
+        let (params, ref mut rng) = xor_setup::<Bls12>();
+        let pvk = prepare_verifying_key(&params.vk);
+        let r = Fr::rand(rng);
+        let s = Fr::rand(rng);
+
+        let assignment = synthesize_circuit(xor_demo(Some(true), Some(false))).unwrap();
+        let mut encoding = vec![];
+        assignment.write(&mut encoding).unwrap();
+        let decoded = ProvingAssignment::<Bls12>::read(&encoding[..]).unwrap();
+
+        let expected = create_proof(xor_demo(Some(true), Some(false)), &params, r, s).unwrap();
+        assert!(create_proof_from_assignment(&params, &assignment, r, s).unwrap() == expected);
+        assert!(create_proof_from_assignment(&params, &decoded, r, s).unwrap() == expected);
+
+        // the same assignment can be proven again with different blinding
+        let other = create_proof_from_assignment(&params, &decoded, s, r).unwrap();
+        assert!(other != expected);
+        assert!(verify_proof(&pvk, &other, &[Fr::one()]).unwrap());
+
+        assert!(ProvingAssignment::<Bls12>::read(&encoding[..encoding.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_inconsistent_assignment_is_rejected() {
+        let assignment = synthesize_circuit(xor_demo::<Bls12>(Some(true), Some(false))).unwrap();
+        let mut encoding = vec![];
+        assignment.write(&mut encoding).unwrap();
+
+        let rejected = |encoding: &[u8]| match ProvingAssignment::<Bls12>::read(encoding) {
+            Err(e) => e.kind() == io::ErrorKind::InvalidData,
+            Ok(_) => false
+        };
+
+        // the three density maps of two elements are followed by A
+        let a_len = u32::from_be_bytes([encoding[15], encoding[16], encoding[17], encoding[18]]);
+        let mut shorter_a = encoding.clone();
+        shorter_a[15..19].copy_from_slice(&(a_len - 1).to_be_bytes());
+        shorter_a.drain(19..(19 + 32));
+        assert!(rejected(&shorter_a));
+
+        // the density of A for one of the two aux variables
+        let mut shorter_density = encoding.clone();
+        shorter_density[0..4].copy_from_slice(&1u32.to_be_bytes());
+        assert!(rejected(&shorter_density));
+    }
+}
+
+mod indexed {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::Field;
+    use crate::tests::xor_demo;
+
+    use super::xor_setup;
+    use super::super::{
+        create_proof,
+        prepare_verifying_key,
+        verify_proof,
+        IndexedParameters,
+        PointValidation,
+        Parameters
+    };
+
+    use rand::Rand;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str, content: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("bellman-{}-{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+
+        path
+    }
+
+    fn setup() -> (Parameters<Bls12>, Vec<u8>, Fr, Fr) {
+        let (params, ref mut rng) = xor_setup::<Bls12>();
+
+        let mut encoding = vec![];
+        params.write_indexed(&mut encoding).unwrap();
+
+        (params, encoding, Fr::rand(rng), Fr::rand(rng))
+    }
+
+    #[test]
+    fn test_indexed_parameters_proof_matches() {
+        let (params, encoding, r, s) = setup();
+        let path = temp_file("indexed-params", &encoding);
+
+        // the index does not break the original format
+        assert!(Parameters::<Bls12>::read(&encoding[..], true).unwrap() == params);
+
+        let expected = create_proof(xor_demo(Some(true), Some(false)), &params, r, s).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        for &validation in &[PointValidation::Unchecked, PointValidation::Upfront, PointValidation::OnRead] {
+            let indexed = IndexedParameters::<Bls12>::open(&path, validation).unwrap();
+            assert!(*indexed.vk() == params.vk);
+
+            let proof = create_proof(xor_demo(Some(true), Some(false)), &indexed, r, s).unwrap();
+            assert!(proof == expected);
+            assert!(verify_proof(&pvk, &proof, &[Fr::one()]).unwrap());
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_indexed_parameters_reject_corruption() {
+        let (params, encoding, r, s) = setup();
+
+        let truncated = temp_file("indexed-truncated", &encoding[..encoding.len() - 1]);
+        assert!(IndexedParameters::<Bls12>::open(&truncated, PointValidation::Unchecked).is_err());
+        std::fs::remove_file(&truncated).unwrap();
+
+        // H does not start right after the verifying key
+        let mut shifted = encoding.clone();
+        let index_at = shifted.len() - 5 * 8;
+        shifted[index_at + 7] ^= 1;
+        let shifted = temp_file("indexed-shifted", &shifted);
+        assert!(IndexedParameters::<Bls12>::open(&shifted, PointValidation::Unchecked).is_err());
+        std::fs::remove_file(&shifted).unwrap();
+
+        // flip a byte of the first point of H
+        let mut vk = vec![];
+        params.vk.write(&mut vk).unwrap();
+        let mut corrupted = encoding.clone();
+        corrupted[vk.len() + 4 + 10] ^= 0xff;
+        let path = temp_file("indexed-corrupted", &corrupted);
+
+        assert!(IndexedParameters::<Bls12>::open(&path, PointValidation::Upfront).is_err());
+
+        let indexed = IndexedParameters::<Bls12>::open(&path, PointValidation::OnRead).unwrap();
+        assert!(create_proof(xor_demo(Some(true), Some(false)), &indexed, r, s).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+mod parameters_verifier {
+    use crate::pairing::bls12_381::Bls12;
+    use crate::pairing::{CurveAffine, CurveProjective};
+    use crate::tests::test_circuits::multiplication_chain;
+
+    use super::super::{
+        generate_random_parameters,
+        verify_parameters,
+        ParameterError,
+        Parameters
+    };
+
+    use rand::{XorShiftRng, SeedableRng};
+    use std::sync::Arc;
+
+    fn setup() -> (Parameters<Bls12>, XorShiftRng) {
+        let mut rng = XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let params = generate_random_parameters(multiplication_chain(8), &mut rng).unwrap();
+
+        (params, rng)
+    }
+
+    fn double<G: CurveAffine>(p: &G) -> G {
+        let mut p = p.into_projective();
+        p.double();
+
+        p.into_affine()
+    }
+
+    #[test]
+    fn test_valid_parameters() {
+        let (params, mut rng) = setup();
+
+        verify_parameters(&params, multiplication_chain::<Bls12>(8), &mut rng).unwrap();
+    }
+
+    #[test]
+    fn test_shape_mismatch() {
+        let (mut params, mut rng) = setup();
+
+        match verify_parameters(&params, multiplication_chain::<Bls12>(9), &mut rng) {
+            Err(ParameterError::ShapeMismatch { query: "L", expected: 9, got: 8 }) => {},
+            e => panic!("unexpected result {:?}", e)
+        }
+
+        let mut h = params.h.as_ref().clone();
+        h.pop();
+        params.h = Arc::new(h);
+
+        match verify_parameters(&params, multiplication_chain::<Bls12>(8), &mut rng) {
+            Err(ParameterError::ShapeMismatch { query: "H", .. }) => {},
+            e => panic!("unexpected result {:?}", e)
+        }
+    }
+
+    #[test]
+    fn test_corrupted_points() {
+        let (params, mut rng) = setup();
+
+        // x is not used in C, so its L element can be checked
+        let mut corrupted = params.clone();
+        let mut l = corrupted.l.as_ref().clone();
+        l[0] = double(&l[0]);
+        corrupted.l = Arc::new(l);
+        match verify_parameters(&corrupted, multiplication_chain::<Bls12>(8), &mut rng) {
+            Err(ParameterError::InconsistentPairing("IC and L queries")) => {},
+            e => panic!("unexpected result {:?}", e)
+        }
+
+        let mut corrupted = params.clone();
+        let mut b_g2 = corrupted.b_g2.as_ref().clone();
+        b_g2[0] = double(&b_g2[0]);
+        corrupted.b_g2 = Arc::new(b_g2);
+        match verify_parameters(&corrupted, multiplication_chain::<Bls12>(8), &mut rng) {
+            Err(ParameterError::InconsistentPairing("B queries in G1 and G2")) => {},
+            e => panic!("unexpected result {:?}", e)
+        }
+
+        let mut corrupted = params.clone();
+        corrupted.vk.delta_g1 = double(&corrupted.vk.delta_g1);
+        match verify_parameters(&corrupted, multiplication_chain::<Bls12>(8), &mut rng) {
+            Err(ParameterError::InconsistentPairing("beta and delta")) => {},
+            e => panic!("unexpected result {:?}", e)
+        }
+
+        let mut corrupted = params.clone();
+        let mut h = corrupted.h.as_ref().clone();
+        h[2] = <Bls12 as crate::pairing::Engine>::G1Affine::zero();
+        corrupted.h = Arc::new(h);
+        match verify_parameters(&corrupted, multiplication_chain::<Bls12>(8), &mut rng) {
+            Err(ParameterError::InvalidPoint { query: "H", index: 2 }) => {},
+            e => panic!("unexpected result {:?}", e)
+        }
+    }
+}
+
+mod encoding {
+    use crate::pairing::bls12_381::{Bls12, G1Affine, G1Compressed};
+    use crate::pairing::{CurveAffine, EncodedPoint};
+    use crate::tests::xor_demo;
+
+    use super::xor_setup;
+    use super::super::{
+        create_random_proof,
+        Proof,
+        VerifyingKey
+    };
+
+    fn setup() -> (VerifyingKey<Bls12>, Proof<Bls12>) {
+        let (params, ref mut rng) = xor_setup::<Bls12>();
+        let proof = create_random_proof(xor_demo(Some(false), Some(false)), &params, rng).unwrap();
+
+        (params.vk, proof)
+    }
+
+    /// Finds a point on the curve which is not in the prime order subgroup.
+    fn point_outside_subgroup() -> G1Affine {
+        for x in 1u8.. {
+            let mut repr = G1Compressed::empty();
+            repr.as_mut()[47] = x;
+            repr.as_mut()[0] |= 0x80;
+
+            if let Ok(p) = repr.into_affine_unchecked() {
+                if p.into_uncompressed().into_affine().is_err() {
+                    return p;
+                }
+            }
+        }
+
+        unreachable!()
+    }
+
+    #[test]
+    fn test_proof_encodings() {
+        let (_, proof) = setup();
+
+        let mut compressed = vec![];
+        proof.write(&mut compressed).unwrap();
+        let mut uncompressed = vec![];
+        proof.write_uncompressed(&mut uncompressed).unwrap();
+        assert_eq!(compressed.len(), 192);
+        assert_eq!(uncompressed.len(), 384);
+
+        assert!(Proof::<Bls12>::read(&compressed[..]).unwrap() == proof);
+        assert!(Proof::<Bls12>::read_unchecked(&compressed[..]).unwrap() == proof);
+        assert!(Proof::<Bls12>::read_uncompressed(&uncompressed[..]).unwrap() == proof);
+        assert!(Proof::<Bls12>::read_uncompressed_unchecked(&uncompressed[..]).unwrap() == proof);
+    }
+
+    #[test]
+    fn test_verifying_key_encodings() {
+        let (vk, _) = setup();
+
+        let mut uncompressed = vec![];
+        vk.write(&mut uncompressed).unwrap();
+        let mut compressed = vec![];
+        vk.write_compressed(&mut compressed).unwrap();
+        assert!(compressed.len() < uncompressed.len());
+
+        assert!(VerifyingKey::<Bls12>::read(&uncompressed[..]).unwrap() == vk);
+        assert!(VerifyingKey::<Bls12>::read_unchecked(&uncompressed[..]).unwrap() == vk);
+        assert!(VerifyingKey::<Bls12>::read_compressed(&compressed[..]).unwrap() == vk);
+        assert!(VerifyingKey::<Bls12>::read_compressed_unchecked(&compressed[..]).unwrap() == vk);
+    }
+
+    #[test]
+    fn test_checked_readers_reject_points_outside_subgroup() {
+        let (mut vk, mut proof) = setup();
+        proof.a = point_outside_subgroup();
+        vk.alpha_g1 = point_outside_subgroup();
+
+        let mut compressed = vec![];
+        proof.write(&mut compressed).unwrap();
+        let mut uncompressed = vec![];
+        proof.write_uncompressed(&mut uncompressed).unwrap();
+
+        assert!(Proof::<Bls12>::read(&compressed[..]).is_err());
+        assert!(Proof::<Bls12>::read_uncompressed(&uncompressed[..]).is_err());
+        // the unchecked readers trust the input
+        assert!(Proof::<Bls12>::read_unchecked(&compressed[..]).unwrap() == proof);
+        assert!(Proof::<Bls12>::read_uncompressed_unchecked(&uncompressed[..]).unwrap() == proof);
+
+        let mut uncompressed = vec![];
+        vk.write(&mut uncompressed).unwrap();
+        let mut compressed = vec![];
+        vk.write_compressed(&mut compressed).unwrap();
+
+        assert!(VerifyingKey::<Bls12>::read(&uncompressed[..]).is_err());
+        assert!(VerifyingKey::<Bls12>::read_compressed(&compressed[..]).is_err());
+        assert!(VerifyingKey::<Bls12>::read_unchecked(&uncompressed[..]).unwrap() == vk);
+        assert!(VerifyingKey::<Bls12>::read_compressed_unchecked(&compressed[..]).unwrap() == vk);
+    }
+
+    #[test]
+    fn test_unchecked_uncompressed_reader_rejects_points_off_curve() {
+        let (_, proof) = setup();
+
+        let mut uncompressed = vec![];
+        proof.write_uncompressed(&mut uncompressed).unwrap();
+        // change y of A
+        uncompressed[95] ^= 0x01;
+
+        assert!(Proof::<Bls12>::read_uncompressed(&uncompressed[..]).is_err());
+        assert!(Proof::<Bls12>::read_uncompressed_unchecked(&uncompressed[..]).is_err());
+    }
+}
+
+mod ethereum {
+    use crate::pairing::bn256::{Bn256, Fr, G1, G1Affine, G2, G2Affine};
+    use crate::pairing::ff::{Field, PrimeField};
+    use crate::pairing::{CurveAffine, CurveProjective};
+    use crate::tests::xor_demo;
+
+    use super::xor_setup;
+    use super::super::{
+        create_proof,
+        create_random_proof,
+        generate_parameters,
+        verify_proof,
+        Proof
+    };
+
+    use super::super::ethereum::{
+        public_inputs_to_eth,
+        public_inputs_from_eth
+    };
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        (0..(hex.len() / 2)).map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap()).collect()
+    }
+
+    fn word(hex: &str) -> [u8; 32] {
+        assert_eq!(hex.len(), 64);
+        let mut word = [0u8; 32];
+        word.copy_from_slice(&bytes(hex));
+
+        word
+    }
+
+    #[test]
+    fn test_generators_known_answer() {
+        let (mut params, _) = xor_setup::<Bn256>();
+        params.vk.alpha_g1 = G1Affine::one();
+        params.vk.beta_g2 = G2Affine::one();
+
+        let vk = params.vk.to_eth_abi();
+
+        // P1() and P2() of the Pairing library used by the Solidity verifier
+        let mut alpha_g1 = vec![];
+        alpha_g1.extend_from_slice(&word("0000000000000000000000000000000000000000000000000000000000000001"));
+        alpha_g1.extend_from_slice(&word("0000000000000000000000000000000000000000000000000000000000000002"));
+        assert_eq!(vk.alpha_g1[..], alpha_g1[..]);
+
+        let mut beta_g2 = vec![];
+        beta_g2.extend_from_slice(&word("198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2"));
+        beta_g2.extend_from_slice(&word("1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed"));
+        beta_g2.extend_from_slice(&word("090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b"));
+        beta_g2.extend_from_slice(&word("12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa"));
+        assert_eq!(vk.beta_g2[..], beta_g2[..]);
+
+        // the largest public input is accepted, the modulus is not
+        let max = word("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000");
+        let modulus = word("30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001");
+        let mut minus_one = Fr::one();
+        minus_one.negate();
+        assert_eq!(public_inputs_from_eth(&[max]).unwrap(), vec![minus_one]);
+        assert_eq!(public_inputs_to_eth(&[minus_one]), vec![max]);
+        assert!(public_inputs_from_eth(&[modulus]).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (params, ref mut rng) = xor_setup::<Bn256>();
+        let proof = create_random_proof(xor_demo(Some(true), Some(false)), &params, rng).unwrap();
+        let inputs = public_inputs_to_eth(&[Fr::from_str("1").unwrap()]);
+
+        let vk = params.vk.to_eth_abi();
+        assert_eq!(vk.ic.len(), 2);
+        let bytes = proof.to_eth_bytes();
+        assert!(Proof::<Bn256>::from_eth_bytes(&bytes).unwrap() == proof);
+
+        // the layout is the same as of the uncompressed points
+        let mut uncompressed = vec![];
+        proof.write_uncompressed(&mut uncompressed).unwrap();
+        assert_eq!(bytes[..], uncompressed[..]);
+
+        let pvk = vk.prepare().unwrap();
+        let proof = Proof::<Bn256>::from_eth_bytes(&bytes).unwrap();
+        assert!(verify_proof(&pvk, &proof, &public_inputs_from_eth(&inputs).unwrap()).unwrap());
+
+        // points that are not on the curve are rejected
+        let mut wrong = bytes;
+        wrong[63] ^= 1;
+        assert!(Proof::<Bn256>::from_eth_bytes(&wrong).is_err());
+
+        // all zeroes are the point at infinity, as for the precompiles
+        let mut infinity = vk.clone();
+        infinity.ic[1] = [0u8; 64];
+        assert!(infinity.prepare().unwrap().ic[1].is_zero());
+        let mut zero_c = proof.clone();
+        zero_c.c = G1Affine::zero();
+        assert_eq!(zero_c.to_eth_bytes()[(64 + 128)..], [0u8; 64][..]);
+        assert!(Proof::<Bn256>::from_eth_bytes(&zero_c.to_eth_bytes()).unwrap() == zero_c);
+    }
+
+    #[test]
+    fn test_proof_known_answer() {
+        let f = |s: &str| Fr::from_str(s).unwrap();
+
+        // alpha = 2, beta = 3, gamma = 5, delta = 7, tau = 11, r = 13, s = 17
+        let params = generate_parameters::<Bn256, _>(xor_demo(None, None), G1::one(), G2::one(), f("2"), f("3"), f("5"), f("7"), f("11")).unwrap();
+        let proof = create_proof(xor_demo(Some(true), Some(false)), &params, f("13"), f("17")).unwrap();
+
+        let vk = params.vk.to_eth_abi();
+        // 2 * P1()
+        assert_eq!(vk.alpha_g1[..], bytes(concat!(
+            "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3",
+            "15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4"
+        ))[..]);
+        assert_eq!(vk.beta_g2[..], bytes(concat!(
+            "1014772f57bb9742735191cd5dcfe4ebbc04156b6878a0a7c9824f32ffb66e85",
+            "06064e784db10e9051e52826e192715e8d7e478cb09a5e0012defa0694fbc7f5",
+            "021e2335f3354bb7922ffcc2f38d3323dd9453ac49b55441452aeaca147711b2",
+            "058e1d5681b5b9e0074b0f9c8d2c68a069b920d74521e79765036d57666c5597"
+        ))[..]);
+        assert_eq!(vk.gamma_g2[..], bytes(concat!(
+            "0a09ccf561b55fd99d1c1208dee1162457b57ac5af3759d50671e510e428b2a1",
+            "2e539c423b302d13f4e5773c603948eaf5db5df8ae8a9a9113708390a06410d8",
+            "19b763513924a736e4eebd0d78c91c1bc1d657fee4214057d21414011cfcc763",
+            "2f8d9f9ab83727c77a2fec063cb7b6e5eb23044ccf535ad49d46d394fb6f6bf6"
+        ))[..]);
+        assert_eq!(vk.delta_g2[..], bytes(concat!(
+            "2903ba015a9abde26a5d081e84551e63be0fd4516e46ee6d593edeba46362455",
+            "224bdc5d4327fcf8ed702e01de1c2f1657a253ba75e32a89c390142aaa28b308",
+            "03c8b7cda6b2dedb7aeeaf5fda464ad17036bea1c4e6f7adbaed1ebe0335e0d8",
+            "1d92fff52a265017eeccb372e37d7a7bd431800eca28dfd82e21e8054114233f"
+        ))[..]);
+        assert_eq!(vk.ic.len(), 2);
+        assert_eq!(vk.ic[0][..], bytes(concat!(
+            "215d04dde03651bdc5b1dd7f4ef32418b7aa86c293bac8b14e4ad2f678c5efe9",
+            "1fe8ececc4495201c991fd042e664c89c3f69bdbfb47a764a11d0594ecdae1ef"
+        ))[..]);
+        assert_eq!(vk.ic[1][..], bytes(concat!(
+            "018b675611145f1c8fb53570eef25cb2e8cdbdaf5cb719c3450cd632626ace74",
+            "0fc4412e37db121fc16d6ee549306ae49b3f37c566166297e729de8e234a531a"
+        ))[..]);
+
+        // A, B, C
+        let expected = bytes(concat!(
+            "13a289480a3d83ba5449f14703412ab2ddfae6e6f441164c07320ccb56e69fbe",
+            "2f5a27e123db7e0e985d7349cba2368e2c0309cc7a4184980feef144406dc5a4",
+            "0c74f0dbcdadeb751ad6e6c0dd0de0c491358e5fae674d1153af1945698666c6",
+            "00ab85e25c6b38338f37b793e45eb1b1682fffd270120f0f7234069abcd60e21",
+            "1ef4df086786ad9c68ccb57f486b67073390ded0763ecd2a4f8bdb7a54376a85",
+            "0f4a931f60dd21c4b5325c68480d6240a839570c3a0f5f5cdca2ba6bc73da784",
+            "2e9ec5bb8f747b2d86b00e35de455fcb74f78f92363439d22709d3f382c5ba3a",
+            "15635dcb5a1a11998363f166ea24773ce2236d66b485fd18669c1629fd5d0c34"
+        ));
+        assert_eq!(proof.to_eth_bytes()[..], expected[..]);
+
+        let mut encoded = [0u8; 256];
+        encoded.copy_from_slice(&expected);
+        let proof = Proof::<Bn256>::from_eth_bytes(&encoded).unwrap();
+        let inputs = public_inputs_from_eth(&[word("0000000000000000000000000000000000000000000000000000000000000001")]).unwrap();
+        assert!(verify_proof(&vk.prepare().unwrap(), &proof, &inputs).unwrap());
+    }
+}
+
+mod generator {
+    use crate::pairing::bn256::Bn256;
+    use crate::tests::test_circuits::multiplication_chain;
+    use crate::worker::Worker;
+
+    use super::super::{
+        generate_parameters_with_worker,
+        generate_insecure_parameters_from_seed,
+        generate_insecure_parameters_from_seed_with_worker,
+        Parameters
+    };
+
+    use rand::{XorShiftRng, SeedableRng, Rng};
+
+    fn generate(depth: usize, worker: &Worker) -> Parameters<Bn256> {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        generate_parameters_with_worker::<Bn256, _>(
+            multiplication_chain(depth),
+            rng.gen(),
+            rng.gen(),
+            rng.gen(),
+            rng.gen(),
+            rng.gen(),
+            rng.gen(),
+            rng.gen(),
+            worker
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_parallel_generation_is_deterministic() {
+        let serial = generate(50, &Worker::new_with_cpus(1));
+
+        for cpus in [2, 3, 8] {
+            assert!(generate(50, &Worker::new_with_cpus(cpus)) == serial);
+        }
+    }
+
+    #[test]
+    fn test_parameters_from_seed_are_reproducible() {
+        let write = |params: Parameters<Bn256>| {
+            let mut bytes = vec![];
+            params.write(&mut bytes).unwrap();
+            bytes
+        };
+
+        let seed = [7u8; 32];
+        let serial = write(generate_insecure_parameters_from_seed_with_worker(multiplication_chain(50), seed, &Worker::new_with_cpus(1)).unwrap());
+
+        for cpus in [2, 3, 8] {
+            let params = generate_insecure_parameters_from_seed_with_worker(multiplication_chain(50), seed, &Worker::new_with_cpus(cpus)).unwrap();
+            assert!(write(params) == serial);
+        }
+
+        let mut other_seed = seed;
+        other_seed[31] ^= 1;
+        let other = write(generate_insecure_parameters_from_seed(multiplication_chain(50), other_seed).unwrap());
+        assert!(other != serial);
+    }
+
+    // a benchmark, run it with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_parameter_generation_bench() {
+        const DEPTH: usize = 4096;
+
+        let start = std::time::Instant::now();
+        let serial = generate(DEPTH, &Worker::new_with_cpus(1));
+        println!("Generated parameters for {} constraints on 1 CPU in {:?}", DEPTH, start.elapsed());
+
+        let worker = Worker::new();
+        let start = std::time::Instant::now();
+        let parallel = generate(DEPTH, &worker);
+        println!("Generated parameters for {} constraints on {} CPUs in {:?}", DEPTH, worker.num_cpus(), start.elapsed());
+
+        assert!(parallel == serial);
+    }
+}
+
+mod input_validation {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::{Field, PrimeField};
+    use crate::tests::xor_demo;
+    use crate::SynthesisError;
+
+    use super::xor_setup;
+    use super::super::{
+        create_random_proof,
+        prepare_verifying_key,
+        verify_proof,
+        verify_proof_with_reprs
+    };
+
+    #[test]
+    fn test_public_input_length() {
+        let (params, ref mut rng) = xor_setup::<Bls12>();
+        let pvk = prepare_verifying_key(&params.vk);
+        let proof = create_random_proof(xor_demo(Some(true), Some(false)), &params, rng).unwrap();
+
+        assert!(verify_proof(&pvk, &proof, &[Fr::one()]).unwrap());
+        assert!(!verify_proof(&pvk, &proof, &[Fr::zero()]).unwrap());
+
+        match verify_proof(&pvk, &proof, &[]) {
+            Err(SynthesisError::InputLengthMismatch { expected: 1, got: 0 }) => {},
+            _ => panic!("too few public inputs")
+        }
+
+        match verify_proof(&pvk, &proof, &[Fr::one(), Fr::one()]) {
+            Err(SynthesisError::InputLengthMismatch { expected: 1, got: 2 }) => {},
+            _ => panic!("too many public inputs")
+        }
+
+        assert!(verify_proof_with_reprs(&pvk, &proof, &[Fr::one().into_repr()]).unwrap());
+
+        match verify_proof_with_reprs(&pvk, &proof, &[Fr::char()]) {
+            Err(SynthesisError::NonCanonicalInput { index: 0 }) => {},
+            _ => panic!("public input is not reduced")
+        }
+
+        match verify_proof_with_reprs(&pvk, &proof, &[]) {
+            Err(SynthesisError::InputLengthMismatch { expected: 1, got: 0 }) => {},
+            _ => panic!("too few public inputs")
+        }
+    }
+}
+
+mod normalization {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::{Field, PrimeField};
+    use crate::{Circuit, ConstraintSystem, LinearCombination, SynthesisError};
+
+    use super::super::{
+        generate_random_parameters,
+        create_proof,
+        prepare_verifying_key,
+        verify_proof
+    };
+
+    use rand::{XorShiftRng, SeedableRng, Rng};
+
+    /// Proves x * (x + 1) = out, with duplicated and unordered terms
+    struct Redundant {
+        x: Option<Fr>,
+        normalize: bool
+    }
+
+    impl Circuit<Bls12> for Redundant {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS
+        ) -> Result<(), SynthesisError>
+        {
+            let x_value = self.x;
+            let out_value = x_value.map(|x| {
+                let mut out = x;
+                out.add_assign(&Fr::one());
+                out.mul_assign(&x);
+                out
+            });
+
+            let x = cs.alloc(|| "x", || x_value.ok_or(SynthesisError::AssignmentMissing))?;
+            let out = cs.alloc_input(|| "out", || out_value.ok_or(SynthesisError::AssignmentMissing))?;
+
+            let two = Fr::from_str("2").unwrap();
+            let mut a = LinearCombination::zero() + x + x - x;
+            let mut b = LinearCombination::zero() + (two, x) + CS::one() - x;
+            let mut c = (LinearCombination::zero() + out) * two - out;
+
+            if self.normalize {
+                a.normalize();
+                b.normalize();
+                c.normalize();
+                assert_eq!((a.len(), b.len(), c.len()), (1, 2, 1));
+            }
+
+            cs.enforce(|| "x * (x + 1) = out", |lc| lc + &a, |lc| lc + &b, |lc| lc + &c);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_normalized_linear_combinations_give_the_same_proof() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let params = generate_random_parameters(Redundant { x: None, normalize: false }, rng).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let x = Fr::from_str("6").unwrap();
+        let (r, s) = (rng.gen(), rng.gen());
+        let proof = create_proof(Redundant { x: Some(x), normalize: false }, &params, r, s).unwrap();
+        let normalized = create_proof(Redundant { x: Some(x), normalize: true }, &params, r, s).unwrap();
+
+        assert!(proof == normalized);
+        assert!(verify_proof(&pvk, &normalized, &[Fr::from_str("42").unwrap()]).unwrap());
+    }
+}
+
+mod error_paths {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::Field;
+    use crate::{Circuit, ConstraintSystem, SynthesisError, TrackedCircuit};
+
+    use super::super::{
+        generate_random_parameters,
+        create_random_proof
+    };
+
+    use rand::{XorShiftRng, SeedableRng};
+    use std::error::Error;
+    use std::io;
+
+    struct Nested {
+        witness: Option<Fr>
+    }
+
+    impl Circuit<Bls12> for Nested {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS
+        ) -> Result<(), SynthesisError>
+        {
+            let mut cs = cs.namespace(|| "a");
+            let mut cs = cs.namespace(|| "b");
+            let mut cs = cs.namespace(|| "c");
+
+            let x = cs.alloc(|| "x", || self.witness.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce(|| "x = x", |lc| lc + x, |lc| lc + CS::one(), |lc| lc + x);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_error_contains_namespace_path() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let params = generate_random_parameters(TrackedCircuit(Nested { witness: None }), rng).unwrap();
+        assert!(create_random_proof(TrackedCircuit(Nested { witness: Some(Fr::one()) }), &params, rng).is_ok());
+
+        let e = match create_random_proof(TrackedCircuit(Nested { witness: None }), &params, rng) {
+            Err(e) => e,
+            Ok(_) => panic!("witness is missing")
+        };
+        assert_eq!(format!("{}", e), "a/b/c/x: an assignment for a variable could not be computed");
+        match e.kind() {
+            SynthesisError::AssignmentMissing => {},
+            _ => panic!("unexpected kind of error")
+        }
+        assert!(e.source().is_some());
+
+        // without tracking the error is unchanged
+        match create_random_proof(Nested { witness: None }, &params, rng) {
+            Err(SynthesisError::AssignmentMissing) => {},
+            _ => panic!("witness is missing")
+        }
+
+        let e = SynthesisError::from(io::Error::other("disk is full"));
+        assert_eq!(format!("{}", e.source().unwrap()), "disk is full");
+    }
+}
+
+mod annotations {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::{Field, PrimeField};
+    use crate::testing::{TestConstraintSystem, ConstraintProfiler};
+    use crate::{Circuit, ConstraintSystem, SynthesisError, maybe_name};
+
+    use super::super::synthesize_circuit;
+
+    /// Allocates `num_bits` booleans, with the names formatted
+    /// eagerly or with `maybe_name!`
+    struct Bits {
+        num_bits: usize,
+        maybe: bool
+    }
+
+    fn alloc_bit<E: crate::pairing::Engine, CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        name: String
+    ) -> Result<(), SynthesisError>
+    {
+        let bit = cs.alloc(|| name, || Ok(E::Fr::one()))?;
+        cs.enforce_unnamed(|lc| lc + CS::one() - bit, |lc| lc + bit, |lc| lc);
+
+        Ok(())
+    }
+
+    impl Circuit<Bls12> for Bits {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS
+        ) -> Result<(), SynthesisError>
+        {
+            for i in 0..self.num_bits {
+                let name = if self.maybe {
+                    maybe_name!(cs, "bit {} of {}", i, self.num_bits)
+                } else {
+                    format!("bit {} of {}", i, self.num_bits)
+                };
+                alloc_bit(cs, name)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_test_constraint_system_records_names() {
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        assert!(cs.wants_annotations());
+
+        {
+            let mut cs = cs.namespace(|| "bits");
+            Bits { num_bits: 3, maybe: true }.synthesize(&mut cs).unwrap();
+        }
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 3);
+        assert_eq!(cs.get("bits/bit 2 of 3"), Fr::one());
+
+        cs.set("bits/bit 1 of 3", Fr::zero());
+        cs.set("bits/bit 2 of 3", Fr::zero());
+        assert!(cs.is_satisfied());
+        cs.set("bits/bit 1 of 3", Fr::from_str("2").unwrap());
+        assert_eq!(cs.which_is_unsatisfied(), Some("bits/unnamed constraint 1".to_owned()));
+    }
+
+    #[test]
+    fn test_skipped_annotations_give_the_same_assignment() {
+        let eager = synthesize_circuit::<Bls12, _>(Bits { num_bits: 1 << 10, maybe: false }).unwrap();
+        let skipped = synthesize_circuit::<Bls12, _>(Bits { num_bits: 1 << 10, maybe: true }).unwrap();
+        assert!(!eager.wants_annotations());
+        assert!(eager == skipped);
+    }
+
+    // a benchmark, run it with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_skipped_annotations_bench() {
+        const NUM_BITS: usize = 1 << 18;
+
+        // the profiler does almost nothing else, so the time is spent in the circuit
+        for &maybe in &[false, true] {
+            let mut cs = ConstraintProfiler::<Bls12>::new();
+            assert!(!cs.wants_annotations());
+            let start = std::time::Instant::now();
+            Bits { num_bits: NUM_BITS, maybe }.synthesize(&mut cs).unwrap();
+            println!("Synthesized {} constraints with maybe_name! = {} in {:?}", cs.num_constraints(), maybe, start.elapsed());
+        }
+    }
+}
+
+mod cached_shape {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::Field;
+    use crate::{Circuit, ConstraintSystem, SynthesisError};
+    use crate::tests::test_circuits::multiplication_chain;
+    use crate::tests::xor_demo;
+
+    use super::xor_setup;
+    use super::super::{
+        generate_random_parameters,
+        create_proof,
+        create_proof_from_assignment,
+        prepare_verifying_key,
+        verify_proof,
+        synthesize_witness,
+        CachedShape
+    };
+
+    use rand::{XorShiftRng, SeedableRng, Rand};
+
+    #[test]
+    fn test_witness_synthesis_gives_the_same_proof() {
+        let (params, ref mut rng) = xor_setup::<Bls12>();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let shape = CachedShape::from_circuit(xor_demo::<Bls12>(None, None)).unwrap();
+        assert_eq!(shape.num_constraints(), 3 + 2);
+
+        for &(a, b) in &[(false, true), (true, true)] {
+            let circuit = || xor_demo::<Bls12>(Some(a), Some(b));
+            let r = Fr::rand(rng);
+            let s = Fr::rand(rng);
+
+            let assignment = synthesize_witness(&shape, circuit()).unwrap();
+            let cached = create_proof_from_assignment(&params, &assignment, r, s).unwrap();
+            let expected = create_proof(circuit(), &params, r, s).unwrap();
+
+            assert!(cached == expected);
+
+            let c = if a ^ b { Fr::one() } else { Fr::zero() };
+            assert!(verify_proof(&pvk, &cached, &[c]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_witness_synthesis_of_a_longer_circuit() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let params = generate_random_parameters(multiplication_chain::<Bls12>(64), rng).unwrap();
+        let shape = CachedShape::from_circuit(multiplication_chain::<Bls12>(64)).unwrap();
+
+        let r = Fr::rand(rng);
+        let s = Fr::rand(rng);
+
+        let assignment = synthesize_witness(&shape, multiplication_chain::<Bls12>(64)).unwrap();
+        let cached = create_proof_from_assignment(&params, &assignment, r, s).unwrap();
+        let expected = create_proof(multiplication_chain::<Bls12>(64), &params, r, s).unwrap();
+
+        assert!(cached == expected);
+    }
+
+    struct Extra;
+
+    impl Circuit<Bls12> for Extra {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS
+        ) -> Result<(), SynthesisError>
+        {
+            xor_demo::<Bls12>(Some(true), Some(false)).synthesize(cs)?;
+            cs.alloc(|| "extra", || Ok(Fr::rand(&mut XorShiftRng::from_seed([1, 2, 3, 4]))))?;
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_witness_synthesis_rejects_a_different_circuit() {
+        let shape = CachedShape::from_circuit(xor_demo::<Bls12>(None, None)).unwrap();
+
+        match synthesize_witness(&shape, Extra) {
+            Err(SynthesisError::ShapeMismatch) => {},
+            _ => panic!("expected a shape mismatch")
+        }
+    }
+
+    #[test]
+    fn test_stored_shape_gives_the_same_proof() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let params = generate_random_parameters(multiplication_chain::<Bls12>(64), rng).unwrap();
+        let shape = CachedShape::from_circuit(multiplication_chain::<Bls12>(64)).unwrap();
+
+        let mut bytes = vec![];
+        shape.write(&mut bytes).unwrap();
+        let stored = CachedShape::<Bls12>::read(&bytes[..]).unwrap();
+        assert_eq!(stored.num_constraints(), shape.num_constraints());
+
+        for truncated in &[1, 4, bytes.len() / 2] {
+            assert!(CachedShape::<Bls12>::read(&bytes[..(bytes.len() - truncated)]).is_err());
+        }
+
+        // the number of terms follows the three density maps and the numbers of variables
+        let mut terms_at = 0;
+        for _ in 0..3 {
+            let len = u32::from_be_bytes([bytes[terms_at], bytes[terms_at + 1], bytes[terms_at + 2], bytes[terms_at + 3]]);
+            terms_at += 4 + (len as usize).div_ceil(8);
+        }
+        terms_at += 8;
+        let mut huge = bytes.clone();
+        huge[terms_at..(terms_at + 4)].copy_from_slice(&u32::MAX.to_be_bytes());
+        huge.truncate(terms_at + 4);
+        assert!(CachedShape::<Bls12>::read(&huge[..]).is_err());
+
+        let r = Fr::rand(rng);
+        let s = Fr::rand(rng);
+
+        let assignment = synthesize_witness(&stored, multiplication_chain::<Bls12>(64)).unwrap();
+        assert!(assignment == synthesize_witness(&shape, multiplication_chain::<Bls12>(64)).unwrap());
+
+        let cached = create_proof_from_assignment(&params, &assignment, r, s).unwrap();
+        let expected = create_proof(multiplication_chain::<Bls12>(64), &params, r, s).unwrap();
+
+        assert!(cached == expected);
+    }
+}
+
+mod canonical {
+    use crate::pairing::CurveProjective;
+    use crate::pairing::bn256::{Bn256, Fr, G1, G2};
+    use crate::pairing::ff::PrimeField;
+
+    use super::super::{
+        Proof,
+        VerifyingKey,
+        CANONICAL_VERSION
+    };
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn g1(k: &str) -> <Bn256 as crate::pairing::Engine>::G1Affine {
+        let mut p = G1::one();
+        p.mul_assign(Fr::from_str(k).unwrap());
+        p.into_affine()
+    }
+
+    fn g2(k: &str) -> <Bn256 as crate::pairing::Engine>::G2Affine {
+        let mut p = G2::one();
+        p.mul_assign(Fr::from_str(k).unwrap());
+        p.into_affine()
+    }
+
+    /// Key with small multiples of the generators
+    fn golden_vk() -> VerifyingKey<Bn256> {
+        VerifyingKey {
+            alpha_g1: g1("1"),
+            beta_g1: g1("2"),
+            beta_g2: g2("1"),
+            gamma_g2: g2("2"),
+            delta_g1: g1("3"),
+            delta_g2: g2("3"),
+            ic: vec![g1("4"), g1("5")]
+        }
+    }
+
+    const GOLDEN_VK: &str = concat!(
+            // version
+            "01",
+            // alpha_g1
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            // beta_g1
+            "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3",
+            // beta_g2
+            "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+            "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+            // gamma_g2
+            "a03e205db4f19b37b60121b83a7333706db86431c6d835849957ed8c3928ad79",
+            "27dc7234fd11d3e8c36c59277c3e6f149d5cd3cfa9a62aee49f8130962b4b3b9",
+            // delta_g1
+            "8769bf9ac56bea3ff40232bcb1b6bd159315d84715b8e679f2d355961915abf0",
+            // delta_g2
+            "1014772f57bb9742735191cd5dcfe4ebbc04156b6878a0a7c9824f32ffb66e85",
+            "06064e784db10e9051e52826e192715e8d7e478cb09a5e0012defa0694fbc7f5",
+            // ic_len
+            "00000002",
+            // ic[0]
+            "06a7b64af8f414bcbeef455b1da5208c9b592b83ee6599824caa6d2ee9141a76",
+            // ic[1]
+            "17c139df0efee0f766bc0204762b774362e4ded88953a39ce849a8a7fa163fa9",
+    );
+
+    #[test]
+    fn test_canonical_vk_bytes() {
+        let bytes = golden_vk().canonical_bytes();
+
+        assert_eq!(bytes.len(), 1 + 3 * 32 + 3 * 64 + 4 + 2 * 32);
+        assert_eq!(bytes[0], CANONICAL_VERSION);
+        assert_eq!(hex(&bytes), GOLDEN_VK);
+
+        let mut vk = golden_vk();
+        vk.ic.pop();
+        assert_ne!(vk.canonical_bytes()[..(1 + 3 * 32 + 3 * 64 + 4)], bytes[..(1 + 3 * 32 + 3 * 64 + 4)]);
+    }
+
+    #[test]
+    fn test_canonical_proof_bytes() {
+        let proof = Proof::<Bn256> { a: g1("1"), b: g2("1"), c: g1("2") };
+        let bytes = proof.canonical_bytes();
+
+        let vk = golden_vk().canonical_bytes();
+        let mut expected = vec![CANONICAL_VERSION];
+        // same points as alpha_g1, beta_g2 and beta_g1 of the key
+        expected.extend_from_slice(&vk[1..33]);
+        expected.extend_from_slice(&vk[65..129]);
+        expected.extend_from_slice(&vk[33..65]);
+        assert_eq!(bytes, expected);
+
+        // the rest is the same encoding as `write`
+        let mut written = vec![];
+        proof.write(&mut written).unwrap();
+        assert_eq!(&bytes[1..], &written[..]);
+    }
+
+    #[cfg(feature = "fingerprint")]
+    #[test]
+    fn test_vk_fingerprints() {
+        use super::super::{Blake2sFingerprint, Keccak256Fingerprint};
+
+        let vk = golden_vk();
+        assert_eq!(hex(&vk.fingerprint::<Blake2sFingerprint>()), "69b71ca88cbcae54d00aebe24dfe9b99ce8f605902785046e53b0cc8ec0f2fe5");
+        assert_eq!(hex(&vk.fingerprint::<Keccak256Fingerprint>()), "b45b13225cfbf22b7310fa2fe89ae32911183040bb39669df6d661c575429038");
+    }
+}
+
+mod proving_session {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::Field;
+    use crate::SynthesisError;
+    use crate::tests::xor_demo;
+    use crate::worker::Worker;
+
+    use super::xor_setup;
+    use super::super::{
+        create_proof,
+        generate_random_parameters,
+        prepare_verifying_key,
+        verify_proof,
+        ProvingSession
+    };
+
+    use crate::{Circuit, ConstraintSystem};
+
+    use rand::{Rand, SeedableRng, XorShiftRng};
+    use std::cell::Cell;
+
+    #[test]
+    fn test_session_proofs_do_not_depend_on_previous_ones() {
+        let (params, ref mut rng) = xor_setup::<Bls12>();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let session = ProvingSession::with_worker(
+            params.clone(),
+            xor_demo::<Bls12>(None, None),
+            Worker::new_with_cpus(2)
+        ).unwrap();
+        assert_eq!(session.shape().num_constraints(), 3 + 2);
+
+        // every witness twice, so a proof after a different witness is checked as well
+        for &(a, b) in &[(true, false), (false, false), (true, false), (false, false)] {
+            let circuit = || xor_demo::<Bls12>(Some(a), Some(b));
+            let r = Fr::rand(rng);
+            let s = Fr::rand(rng);
+
+            let proof = session.create_proof(circuit(), r, s).unwrap();
+            assert!(proof == create_proof(circuit(), &params, r, s).unwrap());
+
+            let c = if a ^ b { Fr::one() } else { Fr::zero() };
+            assert!(verify_proof(&pvk, &proof, &[c]).unwrap());
+        }
+
+        let proof = session.prove(xor_demo::<Bls12>(Some(false), Some(true)), rng).unwrap();
+        assert!(verify_proof(&pvk, &proof, &[Fr::one()]).unwrap());
+
+        match session.prove(xor_demo::<Bls12>(None, Some(true)), rng) {
+            Err(SynthesisError::AssignmentMissing) => {},
+            _ => panic!("expected a missing assignment")
+        }
+    }
+
+    /// x * x = y, counting the linear combinations that are built
+    struct Square<'a> {
+        x: Option<Fr>,
+        built: &'a Cell<usize>
+    }
+
+    impl<'a> Circuit<Bls12> for Square<'a> {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS
+        ) -> Result<(), SynthesisError>
+        {
+            let x = cs.alloc(|| "x", || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+            let y = cs.alloc_input(|| "y", || self.x.map(|mut x| { x.square(); x }).ok_or(SynthesisError::AssignmentMissing))?;
+
+            let built = self.built;
+            cs.enforce(
+                || "x * x = y",
+                |lc| { built.set(built.get() + 1); lc + x },
+                |lc| { built.set(built.get() + 1); lc + x },
+                |lc| { built.set(built.get() + 1); lc + y }
+            );
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_session_only_builds_the_constraints_once() {
+        let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let built = Cell::new(0);
+
+        let params = generate_random_parameters::<Bls12, _, _>(Square { x: None, built: &built }, rng).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        built.set(0);
+        let session = ProvingSession::new(params, Square { x: None, built: &built }).unwrap();
+        assert_eq!(built.get(), 3);
+
+        for _ in 0..2 {
+            let x = Fr::rand(rng);
+            let proof = session.prove(Square { x: Some(x), built: &built }, rng).unwrap();
+
+            let mut y = x;
+            y.square();
+            assert!(verify_proof(&pvk, &proof, &[y]).unwrap());
+        }
+        assert_eq!(built.get(), 3);
+    }
+}
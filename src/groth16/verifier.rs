@@ -4,7 +4,9 @@ use crate::pairing::{
     CurveAffine
 };
 
-use crate::pairing::ff::{PrimeField};
+use crate::pairing::ff::{Field, PrimeField};
+
+use rand::{Rand, Rng};
 
 use super::{
     Proof,
@@ -16,6 +18,9 @@ use crate::{
     SynthesisError
 };
 
+use crate::multiexp::dense_multiexp;
+use crate::worker::Worker;
+
 pub fn prepare_verifying_key<E: Engine>(
     vk: &VerifyingKey<E>
 ) -> PreparedVerifyingKey<E>
@@ -33,15 +38,33 @@ pub fn prepare_verifying_key<E: Engine>(
     }
 }
 
+fn check_input_length<E: Engine>(
+    pvk: &PreparedVerifyingKey<E>,
+    num_inputs: usize
+) -> Result<(), SynthesisError>
+{
+    // the first element of IC is for the "one" input
+    if pvk.ic.is_empty() {
+        return Err(SynthesisError::MalformedVerifyingKey);
+    }
+
+    if num_inputs + 1 != pvk.ic.len() {
+        return Err(SynthesisError::InputLengthMismatch {
+            expected: pvk.ic.len() - 1,
+            got: num_inputs
+        });
+    }
+
+    Ok(())
+}
+
 pub fn verify_proof<'a, E: Engine>(
     pvk: &'a PreparedVerifyingKey<E>,
     proof: &Proof<E>,
     public_inputs: &[E::Fr]
 ) -> Result<bool, SynthesisError>
 {
-    if (public_inputs.len() + 1) != pvk.ic.len() {
-        return Err(SynthesisError::MalformedVerifyingKey);
-    }
+    check_input_length(pvk, public_inputs.len())?;
 
     let mut acc = pvk.ic[0].into_projective();
 
@@ -65,3 +88,99 @@ pub fn verify_proof<'a, E: Engine>(
         ].into_iter())
     ).unwrap() == pvk.alpha_g1_beta_g2)
 }
+
+/// Verifies many proofs against the same verifying key at once. Every
+/// proof is scaled by a random scalar from `rng`, so all of them share a
+/// single final exponentiation, and the public inputs of all proofs are
+/// combined with a single multiexp.
+///
+/// Returns `Ok(false)` if any of the proofs is invalid, and
+/// `SynthesisError::LengthMismatch` if there is not a set of public
+/// inputs for every proof.
+pub fn verify_proofs_batch<E: Engine, R: Rng>(
+    pvk: &PreparedVerifyingKey<E>,
+    proofs: &[Proof<E>],
+    inputs: &[Vec<E::Fr>],
+    rng: &mut R
+) -> Result<bool, SynthesisError>
+{
+    if proofs.len() != inputs.len() {
+        return Err(SynthesisError::LengthMismatch {
+            expected: proofs.len(),
+            got: inputs.len()
+        });
+    }
+
+    for public_inputs in inputs {
+        check_input_length(pvk, public_inputs.len())?;
+    }
+
+    if proofs.is_empty() {
+        return Ok(true);
+    }
+
+    let randomness = (0..proofs.len()).map(|_| E::Fr::rand(rng)).collect::<Vec<_>>();
+
+    // For every proof we have
+    // A_i * B_i + inputs_i * (-gamma) + C_i * (-delta) = alpha * beta
+    // and after scaling by r_i and summing up
+    // sum(r_i * A_i * B_i) + sum(r_i * inputs_i) * (-gamma) + sum(r_i * C_i) * (-delta) = sum(r_i) * alpha * beta
+
+    // coefficient of ic[j] is sum(r_i * inputs_i[j])
+    let mut input_coeffs = vec![E::Fr::zero(); pvk.ic.len()];
+    for (r, public_inputs) in randomness.iter().zip(inputs.iter()) {
+        input_coeffs[0].add_assign(r);
+        for (coeff, input) in input_coeffs.iter_mut().skip(1).zip(public_inputs.iter()) {
+            let mut tmp = *input;
+            tmp.mul_assign(r);
+            coeff.add_assign(&tmp);
+        }
+    }
+
+    let worker = Worker::global();
+
+    let input_coeffs = input_coeffs.into_iter().map(|c| c.into_repr()).collect::<Vec<_>>();
+    let acc_inputs = dense_multiexp(worker, &pvk.ic, &input_coeffs)?;
+
+    let cs = proofs.iter().map(|p| p.c).collect::<Vec<_>>();
+    let randomness_repr = randomness.iter().map(|r| r.into_repr()).collect::<Vec<_>>();
+    let acc_c = dense_multiexp(worker, &cs, &randomness_repr)?;
+
+    let mut prepared = proofs.iter().zip(randomness_repr.iter()).map(|(p, r)| {
+        (p.a.mul(*r).into_affine().prepare(), p.b.prepare())
+    }).collect::<Vec<_>>();
+    prepared.push((acc_inputs.into_affine().prepare(), pvk.neg_gamma_g2.clone()));
+    prepared.push((acc_c.into_affine().prepare(), pvk.neg_delta_g2.clone()));
+
+    let pairs = prepared.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+
+    let mut randomness_sum = E::Fr::zero();
+    for r in randomness.iter() {
+        randomness_sum.add_assign(r);
+    }
+
+    let result = match E::final_exponentiation(&E::miller_loop(pairs.iter())) {
+        Some(result) => result,
+        None => return Ok(false)
+    };
+
+    Ok(result == pvk.alpha_g1_beta_g2.pow(randomness_sum.into_repr()))
+}
+
+/// Same as `verify_proof`, but takes the public inputs as reprs, e.g.
+/// straight from the wire, and rejects those that are not smaller than
+/// the modulus instead of reducing them.
+pub fn verify_proof_with_reprs<E: Engine>(
+    pvk: &PreparedVerifyingKey<E>,
+    proof: &Proof<E>,
+    public_inputs: &[<E::Fr as PrimeField>::Repr]
+) -> Result<bool, SynthesisError>
+{
+    check_input_length(pvk, public_inputs.len())?;
+
+    let public_inputs = public_inputs.iter().enumerate().map(|(index, repr)| {
+        E::Fr::from_repr(*repr).map_err(|_| SynthesisError::NonCanonicalInput { index })
+    }).collect::<Result<Vec<_>, _>>()?;
+
+    verify_proof(pvk, proof, &public_inputs)
+}
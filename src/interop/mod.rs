@@ -0,0 +1,3 @@
+//! Interoperability with circuits written for other tools.
+
+pub mod r1cs_file;
@@ -0,0 +1,371 @@
+//! Import of circuits compiled by circom. `R1csCircuit` reads the binary
+//! `.r1cs` format, which contains the constraints, and optionally the
+//! `.wtns` format, which contains the values of all wires, and replays
+//! them into any `ConstraintSystem`.
+//!
+//! Wire 0 of circom is the constant one and is mapped to `CS::one()`.
+//! It is followed by the public outputs and the public inputs, which are
+//! allocated as inputs in this order, and then by all private wires.
+
+use crate::pairing::{
+    Engine
+};
+
+use crate::pairing::ff::{
+    Field,
+    PrimeField,
+    PrimeFieldRepr,
+    ScalarEngine
+};
+
+use crate::{
+    Circuit,
+    ConstraintSystem,
+    LinearCombination,
+    SynthesisError
+};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read};
+use std::path::Path;
+use byteorder::{LittleEndian, ReadBytesExt};
+
+const R1CS_MAGIC: &[u8; 4] = b"r1cs";
+const R1CS_VERSION: u32 = 1;
+const R1CS_HEADER: u32 = 1;
+const R1CS_CONSTRAINTS: u32 = 2;
+const R1CS_WIRE_TO_LABEL: u32 = 3;
+
+const WTNS_MAGIC: &[u8; 4] = b"wtns";
+const WTNS_VERSION: u32 = 2;
+const WTNS_HEADER: u32 = 1;
+const WTNS_VALUES: u32 = 2;
+
+/// An error that could occur while reading the `.r1cs` or `.wtns` files.
+#[derive(Debug)]
+pub enum R1csFileError {
+    /// Reading a file has failed
+    IoError(io::Error),
+    /// The file does not follow the format
+    InvalidFormat(&'static str),
+    /// The file was written for a field with a different modulus
+    FieldMismatch,
+    /// A coefficient or a value is not smaller than the modulus
+    NonCanonicalValue,
+    /// The witness does not contain a value for every wire
+    WitnessLengthMismatch { expected: usize, got: usize }
+}
+
+impl From<io::Error> for R1csFileError {
+    fn from(e: io::Error) -> R1csFileError {
+        R1csFileError::IoError(e)
+    }
+}
+
+impl Error for R1csFileError {
+    fn description(&self) -> &str {
+        match *self {
+            R1csFileError::IoError(_) => "encountered an I/O error",
+            R1csFileError::InvalidFormat(_) => "invalid file format",
+            R1csFileError::FieldMismatch => "file was written for a different field",
+            R1csFileError::NonCanonicalValue => "value is not smaller than the modulus",
+            R1csFileError::WitnessLengthMismatch { .. } => "witness does not match the number of wires"
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            R1csFileError::IoError(ref e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for R1csFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            R1csFileError::IoError(ref e) => {
+                write!(f, "I/O error: ")?;
+                e.fmt(f)
+            },
+            R1csFileError::InvalidFormat(reason) => {
+                write!(f, "invalid file format: {}", reason)
+            },
+            R1csFileError::WitnessLengthMismatch { expected, got } => {
+                write!(f, "expected values for {} wires, got {}", expected, got)
+            },
+            R1csFileError::FieldMismatch => write!(f, "file was written for a different field"),
+            R1csFileError::NonCanonicalValue => write!(f, "value is not smaller than the modulus")
+        }
+    }
+}
+
+/// Linear combination of wires with their coefficients
+type Terms<E> = Vec<(usize, <E as ScalarEngine>::Fr)>;
+
+/// Circuit read from the files of circom.
+#[derive(Clone)]
+pub struct R1csCircuit<E: Engine> {
+    num_wires: usize,
+    num_public_inputs: usize,
+    constraints: Vec<(Terms<E>, Terms<E>, Terms<E>)>,
+    labels: Vec<u64>,
+    witness: Option<Vec<E::Fr>>
+}
+
+impl<E: Engine> R1csCircuit<E> {
+    /// Reads the constraints from `r1cs_path` and, if it is given, the
+    /// witness from `wtns_path`. Without a witness the circuit can only be
+    /// used to generate parameters.
+    pub fn from_files<P: AsRef<Path>, Q: AsRef<Path>>(
+        r1cs_path: P,
+        wtns_path: Option<Q>
+    ) -> Result<Self, R1csFileError>
+    {
+        let mut circuit = Self::read(BufReader::new(File::open(r1cs_path)?))?;
+
+        if let Some(wtns_path) = wtns_path {
+            circuit.read_witness(BufReader::new(File::open(wtns_path)?))?;
+        }
+
+        Ok(circuit)
+    }
+
+    /// Reads the constraints in the `.r1cs` format.
+    pub fn read<R: Read>(reader: R) -> Result<Self, R1csFileError> {
+        let mut sections = read_sections(reader, R1CS_MAGIC, R1CS_VERSION)?;
+
+        let header = sections.remove(&R1CS_HEADER).ok_or(R1csFileError::InvalidFormat("missing header section"))?;
+        let mut header = Cursor::new(header);
+        read_field::<E, _>(&mut header)?;
+
+        let num_wires = header.read_u32::<LittleEndian>()? as usize;
+        let num_public_outputs = header.read_u32::<LittleEndian>()? as usize;
+        let num_public_inputs = header.read_u32::<LittleEndian>()? as usize;
+        let num_private_inputs = header.read_u32::<LittleEndian>()? as usize;
+        let _num_labels = header.read_u64::<LittleEndian>()?;
+        let num_constraints = header.read_u32::<LittleEndian>()? as usize;
+        expect_end(&header)?;
+
+        let num_public_inputs = num_public_outputs + num_public_inputs;
+        if num_wires < 1 + num_public_inputs + num_private_inputs {
+            return Err(R1csFileError::InvalidFormat("header declares more inputs than wires"));
+        }
+
+        let constraints = sections.remove(&R1CS_CONSTRAINTS).ok_or(R1csFileError::InvalidFormat("missing constraint section"))?;
+        // the counts in the file are not trusted beyond what the section can hold:
+        // a constraint has at least the number of terms of A, B and C, and a term
+        // is a wire and a value
+        let max_constraints = constraints.len() / 12;
+        let max_terms = constraints.len() / (4 + <E::Fr as PrimeField>::Repr::default().as_ref().len() * 8);
+        let mut constraints = Cursor::new(constraints);
+        let mut read_terms = || -> Result<Terms<E>, R1csFileError> {
+            let num_terms = constraints.read_u32::<LittleEndian>()? as usize;
+            let mut terms = Vec::with_capacity(num_terms.min(max_terms));
+            for _ in 0..num_terms {
+                let wire = constraints.read_u32::<LittleEndian>()? as usize;
+                if wire >= num_wires {
+                    return Err(R1csFileError::InvalidFormat("constraint refers to an unknown wire"));
+                }
+                terms.push((wire, read_value::<E, _>(&mut constraints)?));
+            }
+
+            Ok(terms)
+        };
+
+        let mut parsed = Vec::with_capacity(num_constraints.min(max_constraints));
+        for _ in 0..num_constraints {
+            let a = read_terms()?;
+            let b = read_terms()?;
+            let c = read_terms()?;
+            parsed.push((a, b, c));
+        }
+        expect_end(&constraints)?;
+
+        let mut labels = vec![];
+        if let Some(map) = sections.remove(&R1CS_WIRE_TO_LABEL) {
+            if map.len() != num_wires * 8 {
+                return Err(R1csFileError::InvalidFormat("wire to label map does not match the number of wires"));
+            }
+            let mut map = Cursor::new(map);
+            for _ in 0..num_wires {
+                labels.push(map.read_u64::<LittleEndian>()?);
+            }
+        }
+
+        Ok(R1csCircuit {
+            num_wires,
+            num_public_inputs,
+            constraints: parsed,
+            labels,
+            witness: None
+        })
+    }
+
+    /// Reads the values of all wires in the `.wtns` format.
+    pub fn read_witness<R: Read>(&mut self, reader: R) -> Result<(), R1csFileError> {
+        let mut sections = read_sections(reader, WTNS_MAGIC, WTNS_VERSION)?;
+
+        let header = sections.remove(&WTNS_HEADER).ok_or(R1csFileError::InvalidFormat("missing header section"))?;
+        let mut header = Cursor::new(header);
+        read_field::<E, _>(&mut header)?;
+        let num_values = header.read_u32::<LittleEndian>()? as usize;
+        expect_end(&header)?;
+
+        if num_values != self.num_wires {
+            return Err(R1csFileError::WitnessLengthMismatch { expected: self.num_wires, got: num_values });
+        }
+
+        let values = sections.remove(&WTNS_VALUES).ok_or(R1csFileError::InvalidFormat("missing witness section"))?;
+        let mut values = Cursor::new(values);
+        let witness = (0..num_values).map(|_| read_value::<E, _>(&mut values)).collect::<Result<Vec<_>, _>>()?;
+        expect_end(&values)?;
+
+        if witness[0] != E::Fr::one() {
+            return Err(R1csFileError::InvalidFormat("first wire of the witness is not one"));
+        }
+
+        self.witness = Some(witness);
+
+        Ok(())
+    }
+
+    /// Number of wires, including the constant one
+    pub fn num_wires(&self) -> usize {
+        self.num_wires
+    }
+
+    /// Number of public outputs and public inputs, without the constant one
+    pub fn num_public_inputs(&self) -> usize {
+        self.num_public_inputs
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// Labels of the wires, empty if the file has no wire to label map
+    pub fn labels(&self) -> &[u64] {
+        &self.labels
+    }
+
+    /// Values of the public inputs in the order expected by the verifier,
+    /// if the witness was read.
+    pub fn public_inputs(&self) -> Option<&[E::Fr]> {
+        self.witness.as_ref().map(|w| &w[1..(1 + self.num_public_inputs)])
+    }
+}
+
+impl<E: Engine> Circuit<E> for R1csCircuit<E> {
+    fn synthesize<CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS
+    ) -> Result<(), SynthesisError>
+    {
+        let witness = self.witness.as_ref();
+        let value = |i: usize| witness.map(|w| w[i]).ok_or(SynthesisError::AssignmentMissing);
+
+        let mut wires = Vec::with_capacity(self.num_wires);
+        wires.push(CS::one());
+        for i in 1..self.num_wires {
+            let wire = if i <= self.num_public_inputs {
+                cs.alloc_input(|| format!("wire {}", i), || value(i))?
+            } else {
+                cs.alloc(|| format!("wire {}", i), || value(i))?
+            };
+            wires.push(wire);
+        }
+
+        let replay = |lc: LinearCombination<E>, terms: &Terms<E>| -> LinearCombination<E> {
+            terms.iter().fold(lc, |lc, &(wire, coeff)| lc + (coeff, wires[wire]))
+        };
+
+        for (i, (a, b, c)) in self.constraints.iter().enumerate() {
+            cs.enforce(
+                || format!("constraint {}", i),
+                |lc| replay(lc, a),
+                |lc| replay(lc, b),
+                |lc| replay(lc, c)
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the sections of a file in the common format of circom and snarkjs:
+/// magic, version, number of sections, and every section as its type,
+/// its size and its content.
+fn read_sections<R: Read>(
+    mut reader: R,
+    magic: &[u8; 4],
+    version: u32
+) -> Result<HashMap<u32, Vec<u8>>, R1csFileError>
+{
+    let mut file_magic = [0u8; 4];
+    reader.read_exact(&mut file_magic)?;
+    if &file_magic != magic {
+        return Err(R1csFileError::InvalidFormat("unexpected magic"));
+    }
+
+    if reader.read_u32::<LittleEndian>()? != version {
+        return Err(R1csFileError::InvalidFormat("unsupported version"));
+    }
+
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+    let mut sections = HashMap::new();
+    for _ in 0..num_sections {
+        let section_type = reader.read_u32::<LittleEndian>()?;
+        let size = reader.read_u64::<LittleEndian>()?;
+
+        let mut content = vec![];
+        (&mut reader).take(size).read_to_end(&mut content)?;
+        if content.len() as u64 != size {
+            return Err(R1csFileError::InvalidFormat("section is truncated"));
+        }
+
+        if sections.insert(section_type, content).is_some() {
+            return Err(R1csFileError::InvalidFormat("duplicate section"));
+        }
+    }
+
+    Ok(sections)
+}
+
+/// Reads the size of field elements and the modulus, which must be
+/// the ones of `E::Fr`.
+fn read_field<E: Engine, R: Read>(reader: &mut R) -> Result<(), R1csFileError> {
+    let mut expected = vec![];
+    E::Fr::char().write_le(&mut expected)?;
+
+    let size = reader.read_u32::<LittleEndian>()? as usize;
+    if size != expected.len() {
+        return Err(R1csFileError::FieldMismatch);
+    }
+
+    let mut modulus = vec![0u8; size];
+    reader.read_exact(&mut modulus)?;
+    if modulus != expected {
+        return Err(R1csFileError::FieldMismatch);
+    }
+
+    Ok(())
+}
+
+fn read_value<E: Engine, R: Read>(reader: &mut R) -> Result<E::Fr, R1csFileError> {
+    let mut repr = <E::Fr as PrimeField>::Repr::default();
+    repr.read_le(reader)?;
+
+    E::Fr::from_repr(repr).map_err(|_| R1csFileError::NonCanonicalValue)
+}
+
+fn expect_end(cursor: &Cursor<Vec<u8>>) -> Result<(), R1csFileError> {
+    if cursor.position() != cursor.get_ref().len() as u64 {
+        return Err(R1csFileError::InvalidFormat("unexpected data at the end of a section"));
+    }
+
+    Ok(())
+}
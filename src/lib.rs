@@ -20,24 +20,30 @@ pub mod gm17;
 pub mod sonic;
 
 mod group;
-mod source;
-mod multiexp;
+pub mod source;
+pub mod multiexp;
+pub mod backend;
+pub mod observer;
+pub mod testing;
+pub mod interop;
 
 #[cfg(test)]
 mod tests;
 
+mod worker_error;
+
 cfg_if! {
     if #[cfg(feature = "multicore")] {
         #[cfg(feature = "wasm")]
         compile_error!("Multicore feature is not yet compatible with wasm target arch");
 
         mod multicore;
-        mod worker {
+        pub mod worker {
             pub use crate::multicore::*;
         }
     } else {
         mod singlecore;
-        mod worker {
+        pub mod worker {
             pub use crate::singlecore::*;
         }
     }
@@ -55,4 +61,15 @@ use std::env;
 
 fn verbose_flag() -> bool {
     option_env!("BELLMAN_VERBOSE").unwrap_or("0") == "1"
+}
+
+/// Extracts the message from the payload of a caught panic.
+pub(crate) fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
 }
\ No newline at end of file
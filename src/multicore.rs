@@ -13,25 +13,110 @@ use self::futures::{Future, IntoFuture, Poll};
 use self::futures_cpupool::{CpuPool, CpuFuture};
 use self::crossbeam::thread::{Scope};
 
+use std::ops::Range;
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::panic::{self, AssertUnwindSafe};
+
+pub use super::worker_error::WorkerError;
+
+/// Environment variable that overrides the number of threads
+/// used by `Worker::new()` and `Worker::global()`.
+pub const NUM_CPUS_ENV: &str = "BELLMAN_NUM_CPUS";
+
+fn parse_num_cpus(value: Option<String>) -> Option<usize> {
+    value?.trim().parse::<usize>().ok().filter(|&n| n > 0)
+}
+
+/// Number of threads for the default pools, the environment
+/// is read only once per process.
+fn default_num_cpus() -> usize {
+    static NUM_CPUS: OnceLock<usize> = OnceLock::new();
+
+    *NUM_CPUS.get_or_init(|| {
+        parse_num_cpus(std::env::var(NUM_CPUS_ENV).ok()).unwrap_or_else(num_cpus::get)
+    })
+}
+
+fn chunk_size_for(elements: usize, threads: usize) -> usize {
+    if elements < threads {
+        1
+    } else {
+        elements / threads
+    }
+}
+
 #[derive(Clone)]
 pub struct Worker {
     cpus: usize,
-    pool: CpuPool
+    pool: CpuPool,
+    chunks: Option<ChunkCounter>
+}
+
+enum ChunkEvent {
+    Finished,
+    Done
+}
+
+/// Counts the chunks that are spawned on a pool of `observe_chunks`.
+#[derive(Clone)]
+struct ChunkCounter {
+    started: Arc<AtomicUsize>,
+    events: mpsc::Sender<ChunkEvent>
+}
+
+impl ChunkCounter {
+    fn start(&self) -> FinishedChunk {
+        self.started.fetch_add(1, Ordering::SeqCst);
+
+        FinishedChunk(self.events.clone())
+    }
+}
+
+/// Reports the chunk as finished when it is dropped, so panicked
+/// chunks are counted as well.
+struct FinishedChunk(mpsc::Sender<ChunkEvent>);
+
+impl Drop for FinishedChunk {
+    fn drop(&mut self) {
+        let _ = self.0.send(ChunkEvent::Finished);
+    }
+}
+
+impl Default for Worker {
+    fn default() -> Worker {
+        Self::new()
+    }
 }
 
 impl Worker {
-    // We don't expose this outside the library so that
-    // all `Worker` instances have the same number of
-    // CPUs configured.
-    pub(crate) fn new_with_cpus(cpus: usize) -> Worker {
+    /// Creates a pool with exactly `cpus` threads.
+    pub fn new_with_cpus(cpus: usize) -> Worker {
+        assert!(cpus > 0, "worker must have at least one thread");
+
         Worker {
-            cpus: cpus,
-            pool: CpuPool::new(cpus)
+            cpus,
+            pool: CpuPool::new(cpus),
+            chunks: None
         }
     }
 
+    /// Creates a pool with a thread per logical CPU, unless the number
+    /// of threads is overridden by `BELLMAN_NUM_CPUS`.
     pub fn new() -> Worker {
-        Self::new_with_cpus(num_cpus::get())
+        Self::new_with_cpus(default_num_cpus())
+    }
+
+    /// Process-wide pool that is created on the first use. Sharing it
+    /// between subsystems avoids oversubscription of the machine.
+    pub fn global() -> &'static Worker {
+        static GLOBAL: OnceLock<Worker> = OnceLock::new();
+
+        GLOBAL.get_or_init(Worker::new)
+    }
+
+    pub fn num_cpus(&self) -> usize {
+        self.cpus
     }
 
     pub fn log_num_cpus(&self) -> u32 {
@@ -47,11 +132,61 @@ impl Worker {
               R::Item: Send + 'static,
               R::Error: Send + 'static
     {
+        let finished = self.chunks.as_ref().map(ChunkCounter::start);
+
         WorkerFuture {
-            future: self.pool.spawn_fn(f)
+            future: self.pool.spawn_fn(move || {
+                let _finished = finished;
+
+                f()
+            })
         }
     }
 
+    /// Runs `f` with a copy of this pool that counts the chunks of `compute`
+    /// and `try_scope`. `progress(done, total)` is called from a helper
+    /// thread whenever one of them has finished, `total` being the number
+    /// of chunks started so far. All the calls of `progress` have been made
+    /// when this returns.
+    pub fn observe_chunks<F, R, P>(
+        &self,
+        progress: P,
+        f: F
+    ) -> R
+        where F: FnOnce(&Worker) -> R,
+              P: Fn(usize, usize) + Sync
+    {
+        let (events, receiver) = mpsc::channel();
+        let started = Arc::new(AtomicUsize::new(0));
+        let worker = Worker {
+            cpus: self.cpus,
+            pool: self.pool.clone(),
+            chunks: Some(ChunkCounter {
+                started: started.clone(),
+                events: events.clone()
+            })
+        };
+
+        crossbeam::scope(|scope| {
+            let progress = &progress;
+
+            scope.spawn(move |_| {
+                let mut done = 0;
+                while let Ok(ChunkEvent::Finished) = receiver.recv() {
+                    done += 1;
+                    progress(done, started.load(Ordering::SeqCst));
+                }
+            });
+
+            let result = f(&worker);
+            // the chunks of `f` have finished before it returned, so
+            // their events are already in the channel
+            let _ = events.send(ChunkEvent::Done);
+
+            result
+        }).expect("must run")
+    }
+
     pub fn scope<'a, F, R>(
         &self,
         elements: usize,
@@ -59,16 +194,183 @@ impl Worker {
     ) -> R
         where F: FnOnce(&Scope<'a>, usize) -> R
     {
-        let chunk_size = if elements < self.cpus {
-            1
-        } else {
-            elements / self.cpus
-        };
+        let chunk_size = self.get_chunk_size(elements);
 
         crossbeam::scope(|scope| {
             f(scope, chunk_size)
         }).expect("must run")
     }
+
+    /// Same as `scope`, but panics inside of the spawned chunks are caught
+    /// instead of tearing down the whole scope. All the chunks run to the end,
+    /// and the panic of the first failed chunk (in the order they were
+    /// spawned) is returned as an error.
+    pub fn try_scope<'a, F, R>(
+        &self,
+        elements: usize,
+        f: F
+    ) -> Result<R, WorkerError>
+        where F: FnOnce(&ChunkScope<'_, 'a>, usize) -> R
+    {
+        let chunk_size = self.get_chunk_size(elements);
+        let errors: Arc<Mutex<Vec<WorkerError>>> = Arc::new(Mutex::new(vec![]));
+
+        let result = crossbeam::scope(|scope| {
+            let scope = ChunkScope {
+                scope,
+                next_chunk: AtomicUsize::new(0),
+                errors: errors.clone(),
+                chunks: self.chunks.clone()
+            };
+
+            f(&scope, chunk_size)
+        }).expect("panics are caught inside of the chunks");
+
+        let first_error = errors.lock().expect("must lock").iter().min_by_key(|e| e.chunk).cloned();
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(result)
+        }
+    }
+
+    /// Splits `data` into chunks, maps every chunk on the pool and reduces the
+    /// results of the chunks in order. The mapping function also receives the
+    /// index of the first element of the chunk. Empty `data` is mapped as a
+    /// single empty chunk.
+    pub fn map_reduce<T, R, M, Rd>(
+        &self,
+        data: &[T],
+        map: M,
+        reduce: Rd
+    ) -> Result<R, WorkerError>
+        where T: Sync,
+              R: Send,
+              M: Fn(usize, &[T]) -> R + Sync,
+              Rd: Fn(R, R) -> R
+    {
+        if data.is_empty() {
+            return Ok(map(0, data));
+        }
+
+        let ranges = self.get_ranges(data.len());
+        let mut results: Vec<Option<R>> = ranges.iter().map(|_| None).collect();
+
+        self.try_scope(data.len(), |scope, _| {
+            let map = &map;
+
+            for (range, result) in ranges.iter().zip(results.iter_mut()) {
+                let start = range.start;
+                let chunk = &data[range.clone()];
+
+                scope.spawn(move || {
+                    *result = Some(map(start, chunk));
+                });
+            }
+        })?;
+
+        let reduced = results.into_iter()
+                            .map(|r| r.expect("all the chunks have completed"))
+                            .reduce(reduce)
+                            .expect("there is at least one chunk");
+
+        Ok(reduced)
+    }
+
+    /// Splits `data` into chunks and modifies every chunk on the pool. The
+    /// function also receives the index of the first element of the chunk.
+    pub fn map_in_place<T, F>(
+        &self,
+        data: &mut [T],
+        f: F
+    ) -> Result<(), WorkerError>
+        where T: Send,
+              F: Fn(usize, &mut [T]) + Sync
+    {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let chunk_size = self.get_chunk_size(data.len());
+
+        self.try_scope(data.len(), |scope, _| {
+            let f = &f;
+
+            for (i, chunk) in data.chunks_mut(chunk_size).enumerate() {
+                scope.spawn(move || {
+                    f(i * chunk_size, chunk);
+                });
+            }
+        })
+    }
+
+    /// Ranges of the chunks that `scope` splits `elements` into.
+    pub fn get_ranges(&self, elements: usize) -> Vec<Range<usize>> {
+        let chunk_size = self.get_chunk_size(elements);
+
+        (0..elements).step_by(chunk_size)
+                     .map(|start| start..std::cmp::min(start + chunk_size, elements))
+                     .collect()
+    }
+
+    /// Same as `scope`, but `elements` are split between at most
+    /// `max_parallelism` threads of the pool.
+    pub fn scope_with_limit<'a, F, R>(
+        &self,
+        max_parallelism: usize,
+        elements: usize,
+        f: F
+    ) -> R
+        where F: FnOnce(&Scope<'a>, usize) -> R
+    {
+        let chunk_size = self.get_chunk_size_with_limit(max_parallelism, elements);
+
+        crossbeam::scope(|scope| {
+            f(scope, chunk_size)
+        }).expect("must run")
+    }
+
+    /// Size of the chunks that `scope` splits `elements` into.
+    pub fn get_chunk_size(&self, elements: usize) -> usize {
+        chunk_size_for(elements, self.cpus)
+    }
+
+    /// Size of the chunks that `scope_with_limit` splits `elements` into.
+    pub fn get_chunk_size_with_limit(&self, max_parallelism: usize, elements: usize) -> usize {
+        let threads = std::cmp::max(1, std::cmp::min(max_parallelism, self.cpus));
+
+        chunk_size_for(elements, threads)
+    }
+}
+
+/// Scope of `Worker::try_scope`. Every spawned closure is a separate chunk,
+/// the chunks are numbered in the order they are spawned.
+pub struct ChunkScope<'s, 'a> {
+    scope: &'s Scope<'a>,
+    next_chunk: AtomicUsize,
+    errors: Arc<Mutex<Vec<WorkerError>>>,
+    chunks: Option<ChunkCounter>
+}
+
+impl<'s, 'a> ChunkScope<'s, 'a> {
+    pub fn spawn<F>(&self, f: F)
+        where F: FnOnce() + Send + 'a
+    {
+        let chunk = self.next_chunk.fetch_add(1, Ordering::SeqCst);
+        let errors = self.errors.clone();
+        let finished = self.chunks.as_ref().map(ChunkCounter::start);
+
+        self.scope.spawn(move |_| {
+            let _finished = finished;
+
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+                errors.lock().expect("must lock").push(WorkerError {
+                    chunk,
+                    message: crate::panic_message(payload)
+                });
+            }
+        });
+    }
 }
 
 pub struct WorkerFuture<T, E> {
@@ -108,3 +410,186 @@ fn test_log2_floor() {
     assert_eq!(log2_floor(7), 2);
     assert_eq!(log2_floor(8), 3);
 }
+
+#[test]
+fn test_parse_num_cpus() {
+    assert_eq!(parse_num_cpus(None), None);
+    assert_eq!(parse_num_cpus(Some("4".to_string())), Some(4));
+    assert_eq!(parse_num_cpus(Some(" 16\n".to_string())), Some(16));
+    assert_eq!(parse_num_cpus(Some("0".to_string())), None);
+    assert_eq!(parse_num_cpus(Some("many".to_string())), None);
+}
+
+#[test]
+fn test_chunk_size_with_limit() {
+    let worker = Worker::new_with_cpus(8);
+    assert_eq!(worker.num_cpus(), 8);
+
+    assert_eq!(worker.get_chunk_size(100), 12);
+    assert_eq!(worker.get_chunk_size(5), 1);
+    assert_eq!(worker.get_chunk_size(0), 1);
+
+    // limit smaller than the pool gives larger chunks
+    assert_eq!(worker.get_chunk_size_with_limit(2, 100), 50);
+    assert_eq!(worker.get_chunk_size_with_limit(3, 100), 33);
+    assert_eq!(worker.get_chunk_size_with_limit(3, 2), 1);
+    // limit larger than the pool is capped by the pool
+    assert_eq!(worker.get_chunk_size_with_limit(64, 100), 12);
+    // zero limit still makes progress
+    assert_eq!(worker.get_chunk_size_with_limit(0, 100), 100);
+
+    let chunk = worker.scope_with_limit(2, 100, |_, chunk| chunk);
+    assert_eq!(chunk, 50);
+
+    // every element is covered exactly once
+    let mut data = vec![0u32; 100];
+    worker.scope_with_limit(3, data.len(), |scope, chunk| {
+        for c in data.chunks_mut(chunk) {
+            scope.spawn(move |_| {
+                for v in c.iter_mut() {
+                    *v += 1;
+                }
+            });
+        }
+    });
+    assert!(data.iter().all(|&v| v == 1));
+}
+
+#[test]
+fn test_global_worker_is_shared() {
+    let a = Worker::global() as *const Worker;
+    let b = Worker::global() as *const Worker;
+
+    assert_eq!(a, b);
+    assert!(Worker::global().num_cpus() > 0);
+}
+
+#[test]
+fn test_try_scope_reports_panicked_chunk() {
+    let worker = Worker::new_with_cpus(8);
+
+    let mut data = vec![0u32; 64];
+    let result = worker.try_scope(data.len(), |scope, chunk| {
+        assert_eq!(chunk, 8);
+        for (i, c) in data.chunks_mut(chunk).enumerate() {
+            scope.spawn(move || {
+                if i == 3 {
+                    panic!("chunk {} has failed", i);
+                }
+                for v in c.iter_mut() {
+                    *v = 1;
+                }
+            });
+        }
+    });
+
+    let err = result.unwrap_err();
+    assert_eq!(err.chunk, 3);
+    assert_eq!(err.message, "chunk 3 has failed");
+
+    // other chunks have completed
+    for (i, c) in data.chunks(8).enumerate() {
+        assert!(c.iter().all(|&v| v == if i == 3 { 0 } else { 1 }));
+    }
+
+    // the pool is still usable
+    let total = AtomicUsize::new(0);
+    worker.try_scope(4, |scope, _| {
+        for i in 0..4 {
+            let total = &total;
+            scope.spawn(move || {
+                total.fetch_add(i, Ordering::SeqCst);
+            });
+        }
+    }).unwrap();
+    assert_eq!(total.load(Ordering::SeqCst), 6);
+}
+
+#[test]
+fn test_get_ranges() {
+    let worker = Worker::new_with_cpus(4);
+
+    assert_eq!(worker.get_ranges(0), vec![]);
+    assert_eq!(worker.get_ranges(3), vec![0..1, 1..2, 2..3]);
+    assert_eq!(worker.get_ranges(10), vec![0..2, 2..4, 4..6, 6..8, 8..10]);
+    assert_eq!(worker.get_ranges(12), vec![0..3, 3..6, 6..9, 9..12]);
+}
+
+#[test]
+fn test_map_reduce() {
+    let worker = Worker::new_with_cpus(4);
+
+    let sum = |data: &[u64]| {
+        worker.map_reduce(data, |_, chunk| chunk.iter().sum::<u64>(), |a, b| a + b).unwrap()
+    };
+
+    assert_eq!(sum(&[]), 0);
+    assert_eq!(sum(&[1, 2, 3]), 6);
+
+    let data = (0..100_000u64).collect::<Vec<_>>();
+    assert_eq!(sum(&data), 100_000 * 99_999 / 2);
+
+    // chunks are reduced in order and know their offsets
+    let data = (0..1000usize).collect::<Vec<_>>();
+    let reduced = worker.map_reduce(&data, |start, chunk| {
+        assert_eq!(chunk[0], start);
+        chunk.to_vec()
+    }, |mut a, b| {
+        a.extend(b);
+        a
+    }).unwrap();
+    assert_eq!(reduced, data);
+
+    let err = worker.map_reduce(&data, |start, _| {
+        if start > 0 {
+            panic!("not the first chunk");
+        }
+    }, |_, _| ()).unwrap_err();
+    assert_eq!(err.chunk, 1);
+}
+
+#[test]
+fn test_map_in_place() {
+    let worker = Worker::new_with_cpus(4);
+
+    let mut data: Vec<usize> = vec![];
+    worker.map_in_place(&mut data, |_, _| unreachable!()).unwrap();
+
+    for &len in &[1usize, 3, 100_000] {
+        let mut data = vec![0usize; len];
+        worker.map_in_place(&mut data, |start, chunk| {
+            for (i, v) in chunk.iter_mut().enumerate() {
+                *v = start + i;
+            }
+        }).unwrap();
+
+        assert!(data.iter().enumerate().all(|(i, &v)| i == v));
+    }
+}
+
+#[test]
+fn test_observe_chunks() {
+    let worker = Worker::new_with_cpus(4);
+    let events = Mutex::new(vec![]);
+
+    let sum = worker.observe_chunks(|done, total| events.lock().unwrap().push((done, total)), |worker| {
+        let data = (0..100u64).collect::<Vec<_>>();
+        let sum = worker.map_reduce(&data, |_, chunk| chunk.iter().sum::<u64>(), |a, b| a + b).unwrap();
+        let futures = (0..3u64).map(|i| worker.compute(move || Ok::<_, ()>(i))).collect::<Vec<_>>();
+
+        sum + futures.into_iter().map(|f| f.wait().unwrap()).sum::<u64>()
+    });
+    assert_eq!(sum, 4950 + 3);
+
+    // 4 chunks of map_reduce and 3 computations
+    let events = events.into_inner().unwrap();
+    assert_eq!(events.len(), 7);
+    for (i, &(done, total)) in events.iter().enumerate() {
+        assert_eq!(done, i + 1);
+        assert!(done <= total && total <= 7);
+    }
+    assert_eq!(events[6], (7, 7));
+
+    // the original pool is not observed
+    assert!(worker.chunks.is_none());
+}
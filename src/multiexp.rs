@@ -1,6 +1,7 @@
 use crate::pairing::{
     CurveAffine,
     CurveProjective,
+    EncodedPoint,
     Engine
 };
 
@@ -11,12 +12,136 @@ use crate::pairing::ff::{
     ScalarEngine};
 
 use std::sync::Arc;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use byteorder::{BigEndian, WriteBytesExt, ReadBytesExt};
 use super::source::*;
 use futures::{Future};
-use super::worker::Worker;
+use super::worker::{Worker, WorkerError, WorkerFuture};
 
 use super::SynthesisError;
 
+/// Exponents of a multiexp that have a base, split by their value: zeros
+/// are dropped, ones are added up directly and only the rest go through
+/// the buckets. The bases are not copied, only their positions in the
+/// source are kept.
+///
+/// Every region of the buckets goes over the general exponents again, so
+/// they are listed once instead of comparing all the exponents with zero
+/// and one and walking the density in every region. The indices are `u32`,
+/// like the lengths in the serialized assignments, which makes the list
+/// 8 bytes per general exponent.
+struct Partition {
+    // positions of the bases of the ones
+    ones: Vec<u32>,
+    // indices of the other exponents and positions of their bases
+    general: Vec<(u32, u32)>,
+    // number of bases, including the ones of the zeros
+    num_bases: usize
+}
+
+impl Partition {
+    /// Partitions the exponents on the `pool`, every chunk has the same
+    /// number of bases and finds its elements with `QueryDensity::select`.
+    /// Without `split` all the exponents with a base are general.
+    fn new<Q, D, R>(
+        pool: &Worker,
+        density_map: &D,
+        exponents: &[R],
+        zero: R,
+        one: R,
+        split: bool
+    ) -> Result<Self, SynthesisError>
+        where for<'a> &'a Q: QueryDensity,
+              D: Sync + AsRef<Q>,
+              R: PrimeFieldRepr
+    {
+        if u32::try_from(exponents.len()).is_err() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "too many exponents for a multiexp").into());
+        }
+
+        // the density map must cover every exponent, or `rank` runs past its end
+        if let Some(query_size) = density_map.as_ref().get_query_size() {
+            if query_size != exponents.len() {
+                return Err(SynthesisError::LengthMismatch {
+                    expected: query_size,
+                    got: exponents.len()
+                });
+            }
+        }
+
+        let num_bases = density_map.as_ref().rank(exponents.len());
+        let chunks = pool.get_ranges(num_bases);
+
+        let partition = pool.map_reduce(&chunks, |_, chunks| {
+            let mut partition = Partition {
+                ones: vec![],
+                general: vec![],
+                num_bases: 0
+            };
+
+            for bases in chunks {
+                let density = density_map.as_ref();
+                let start = density.select(bases.start).expect("the base is in the query");
+                let end = density.select(bases.end).unwrap_or(exponents.len());
+
+                let mut position = bases.start as u32;
+                for (i, exp) in exponents[start..end].iter().enumerate() {
+                    if !density.has_base(start + i) {
+                        continue;
+                    }
+
+                    if split && *exp == zero {
+                        // no need to touch the base
+                    } else if split && *exp == one {
+                        partition.ones.push(position);
+                    } else {
+                        partition.general.push(((start + i) as u32, position));
+                    }
+
+                    position += 1;
+                }
+
+                partition.num_bases += bases.len();
+            }
+
+            partition
+        }, |mut partition, next| {
+            partition.ones.extend(next.ones);
+            partition.general.extend(next.general);
+            partition.num_bases += next.num_bases;
+
+            partition
+        })?;
+
+        Ok(partition)
+    }
+}
+
+/// `Worker::compute` for a chunk of a multiexp, a panic of the chunk is
+/// returned as `SynthesisError::WorkerPanicked` instead of resuming it
+/// when the future is waited for.
+fn compute_chunk<T, F>(
+    pool: &Worker,
+    chunk: usize,
+    f: F
+) -> WorkerFuture<T, SynthesisError>
+    where F: FnOnce() -> Result<T, SynthesisError> + Send + 'static,
+          T: Send + 'static
+{
+    pool.compute(move || {
+        panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+            Err(WorkerError {
+                chunk,
+                message: crate::panic_message(payload)
+            }.into())
+        })
+    })
+}
+
 /// This genious piece of code works in the following way:
 /// - choose `c` - the bit length of the region that one thread works on
 /// - make `2^c - 1` buckets and initialize them with `G = infinity` (that's equivalent of zero)
@@ -38,7 +163,7 @@ use super::SynthesisError;
 /// - double the highest accumulator enough times, add to the next one, double the result, add the next accumulator, continue
 /// 
 /// Demo why it works:
-/// ```
+/// ```text
 ///     a * G + b * H = (a_2 * (2^c)^2 + a_1 * (2^c)^1 + a_0) * G + (b_2 * (2^c)^2 + b_1 * (2^c)^1 + b_0) * H
 /// ```
 /// - make buckets over `0` labeled coefficients
@@ -47,34 +172,33 @@ use super::SynthesisError;
 /// - accumulators over each set of buckets will have an implicit factor of `(2^c)^i`, so before summing thme up
 /// "higher" accumulators must be doubled `c` times
 ///
-fn multiexp_inner<Q, D, G, S>(
+/// Every region of the buckets is a chunk, numbered from the lowest one,
+/// and the chunks of `sum_ones` follow them.
+fn multiexp_inner<G, S>(
     pool: &Worker,
     bases: S,
-    density_map: D,
     exponents: Arc<Vec<<G::Scalar as PrimeField>::Repr>>,
-    // exponents: Arc<Vec<<<G::Engine as Engine>::Fr as PrimeField>::Repr>>,
+    partition: Arc<Partition>,
     mut skip: u32,
-    c: u32,
-    handle_trivial: bool
+    c: u32
 ) -> Box<Future<Item=<G as CurveAffine>::Projective, Error=SynthesisError>>
-    where for<'a> &'a Q: QueryDensity,
-          D: Send + Sync + 'static + Clone + AsRef<Q>,
-          G: CurveAffine,
+    where G: CurveAffine,
           S: SourceBuilder<G>
 {
     // Perform this region of the multiexp
     let this = {
         let bases = bases.clone();
         let exponents = exponents.clone();
-        let density_map = density_map.clone();
+        let partition = partition.clone();
 
         // This looks like a Pippenger’s algorithm
-        pool.compute(move || {
+        compute_chunk(pool, (skip / c) as usize, move || {
             // Accumulate the result
             let mut acc = G::Projective::zero();
 
             // Build a source for the bases
             let mut bases = bases.new();
+            let mut position = 0;
 
             // Create buckets to place remainders s mod 2^c,
             // it will be 2^c - 1 buckets (no bucket for zeroes)
@@ -82,37 +206,25 @@ fn multiexp_inner<Q, D, G, S>(
             // Create space for the buckets
             let mut buckets = vec![<G as CurveAffine>::Projective::zero(); (1 << c) - 1];
 
-            let zero = <G::Engine as ScalarEngine>::Fr::zero().into_repr();
-            let one = <G::Engine as ScalarEngine>::Fr::one().into_repr();
-
-            // Sort the bases into buckets
-            for (&exp, density) in exponents.iter().zip(density_map.as_ref().iter()) {
-                // Go over density and exponents
-                if density {
-                    if exp == zero {
-                        bases.skip(1)?;
-                    } else if exp == one {
-                        if handle_trivial {
-                            bases.add_assign_mixed(&mut acc)?;
-                        } else {
-                            bases.skip(1)?;
-                        }
-                    } else {
-                        // Place multiplication into the bucket: Separate s * P as 
-                        // (s/2^c) * P + (s mod 2^c) P
-                        // First multiplication is c bits less, so one can do it,
-                        // sum results from different buckets and double it c times,
-                        // then add with (s mod 2^c) P parts
-                        let mut exp = exp;
-                        exp.shr(skip);
-                        let exp = exp.as_ref()[0] % (1 << c);
-
-                        if exp != 0 {
-                            bases.add_assign_mixed(&mut buckets[(exp - 1) as usize])?;
-                        } else {
-                            bases.skip(1)?;
-                        }
+            // Sort the bases into buckets, zeros and ones are already
+            // taken care of by the partition
+            for &(i, base) in &partition.general {
+                let (i, base) = (i as usize, base as usize);
+                // Place multiplication into the bucket: Separate s * P as
+                // (s/2^c) * P + (s mod 2^c) P
+                // First multiplication is c bits less, so one can do it,
+                // sum results from different buckets and double it c times,
+                // then add with (s mod 2^c) P parts
+                let mut exp = exponents[i];
+                exp.shr(skip);
+                let exp = exp.as_ref()[0] % (1 << c);
+
+                if exp != 0 {
+                    if base > position {
+                        bases.skip(base - position)?;
                     }
+                    bases.add_assign_mixed(&mut buckets[(exp - 1) as usize])?;
+                    position = base + 1;
                 }
             }
 
@@ -139,7 +251,7 @@ fn multiexp_inner<Q, D, G, S>(
         // There's another region more significant. Calculate and join it with
         // this region recursively.
         Box::new(
-            this.join(multiexp_inner(pool, bases, density_map, exponents, skip, c, false))
+            this.join(multiexp_inner(pool, bases, exponents, partition, skip, c))
                 .map(move |(this, mut higher)| {
                     for _ in 0..c {
                         higher.double();
@@ -153,8 +265,51 @@ fn multiexp_inner<Q, D, G, S>(
     }
 }
 
-/// Perform multi-exponentiation. The caller is responsible for ensuring the
-/// query size is the same as the number of exponents.
+/// Adds up the bases of the exponents that are one, in chunks on the `pool`.
+fn sum_ones<G, S>(
+    pool: &Worker,
+    bases: S,
+    partition: Arc<Partition>,
+    first_chunk: usize
+) -> Box<dyn Future<Item=<G as CurveAffine>::Projective, Error=SynthesisError>>
+    where G: CurveAffine,
+          S: SourceBuilder<G>
+{
+    let sums = pool.get_ranges(partition.ones.len()).into_iter().enumerate().map(|(i, range)| {
+        let bases = bases.clone();
+        let partition = partition.clone();
+
+        compute_chunk(pool, first_chunk + i, move || {
+            let mut acc = G::Projective::zero();
+
+            let mut bases = bases.new();
+            let mut position = 0;
+
+            for &base in &partition.ones[range] {
+                let base = base as usize;
+                if base > position {
+                    bases.skip(base - position)?;
+                }
+                bases.add_assign_mixed(&mut acc)?;
+                position = base + 1;
+            }
+
+            Ok(acc)
+        })
+    }).collect::<Vec<_>>();
+
+    Box::new(futures::future::join_all(sums).map(|sums| {
+        let mut acc = G::Projective::zero();
+        for sum in sums {
+            acc.add_assign(&sum);
+        }
+
+        acc
+    }))
+}
+
+/// Perform multi-exponentiation. Fails with `SynthesisError::LengthMismatch`
+/// if the query size is not the same as the number of exponents.
 pub fn multiexp<Q, D, G, S>(
     pool: &Worker,
     bases: S,
@@ -166,20 +321,55 @@ pub fn multiexp<Q, D, G, S>(
           G: CurveAffine,
           S: SourceBuilder<G>
 {
-    let c = if exponents.len() < 32 {
+    multiexp_with_partition(pool, bases, density_map, exponents, true)
+}
+
+/// `multiexp`, where zero and one exponents go through the buckets like
+/// the rest without `split`.
+fn multiexp_with_partition<Q, D, G, S>(
+    pool: &Worker,
+    bases: S,
+    density_map: D,
+    exponents: Arc<Vec<<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr>>,
+    split: bool
+) -> Box<dyn Future<Item=<G as CurveAffine>::Projective, Error=SynthesisError>>
+    where for<'a> &'a Q: QueryDensity,
+          D: Send + Sync + 'static + Clone + AsRef<Q>,
+          G: CurveAffine,
+          S: SourceBuilder<G>
+{
+    let zero = <G::Engine as ScalarEngine>::Fr::zero().into_repr();
+    let one = <G::Engine as ScalarEngine>::Fr::one().into_repr();
+
+    let partition = match Partition::new(pool, &density_map, &exponents, zero, one, split) {
+        Ok(partition) => Arc::new(partition),
+        Err(e) => return Box::new(futures::future::err(e))
+    };
+
+    // The bases of the zeros are never read, but the source must still
+    // have them, like when every base was read or skipped in turn.
+    if partition.num_bases > 0 {
+        let mut source = bases.clone().new();
+        if let Err(e) = source.skip(partition.num_bases - 1).and_then(|_| source.skip(1)) {
+            return Box::new(futures::future::err(e));
+        }
+    }
+
+    let c = if partition.general.len() < 32 {
         3u32
     } else {
-        (f64::from(exponents.len() as u32)).ln().ceil() as u32
+        (f64::from(partition.general.len() as u32)).ln().ceil() as u32
     };
 
-    if let Some(query_size) = density_map.as_ref().get_query_size() {
-        // If the density map has a known query size, it should not be
-        // inconsistent with the number of exponents.
+    Box::new(
+        multiexp_inner(pool, bases.clone(), exponents, partition.clone(), 0, c)
+            .join(sum_ones(pool, bases, partition, <G::Engine as ScalarEngine>::Fr::NUM_BITS.div_ceil(c) as usize))
+            .map(|(mut acc, ones)| {
+                acc.add_assign(&ones);
 
-        assert!(query_size == exponents.len());
-    }
-
-    multiexp_inner(pool, bases, density_map, exponents, 0, c, true)
+                acc
+            })
+    )
 }
 
 
@@ -213,75 +403,46 @@ fn dense_multiexp_inner<G: CurveAffine>(
     handle_trivial: bool
 ) -> Result<<G as CurveAffine>::Projective, SynthesisError>
 {   
-    use std::sync::{Mutex};
     // Perform this region of the multiexp. We use a different strategy - go over region in parallel,
     // then over another region, etc. No Arc required
-    let this = {
-        // let mask = (1u64 << c) - 1u64;
-        let this_region = Mutex::new(<G as CurveAffine>::Projective::zero());
-        let arc = Arc::new(this_region);
-        pool.scope(bases.len(), |scope, chunk| {
-            for (base, exp) in bases.chunks(chunk).zip(exponents.chunks(chunk)) {
-                let this_region_rwlock = arc.clone();
-                // let handle = 
-                scope.spawn(move |_| {
-                    let mut buckets = vec![<G as CurveAffine>::Projective::zero(); (1 << c) - 1];
-                    // Accumulate the result
-                    let mut acc = G::Projective::zero();
-                    let zero = <G::Engine as ScalarEngine>::Fr::zero().into_repr();
-                    let one = <G::Engine as ScalarEngine>::Fr::one().into_repr();
-
-                    for (base, &exp) in base.iter().zip(exp.iter()) {
-                        // let index = (exp.as_ref()[0] & mask) as usize;
-
-                        // if index != 0 {
-                        //     buckets[index - 1].add_assign_mixed(base);
-                        // }
-
-                        // exp.shr(c as u32);
-
-                        if exp != zero {
-                            if exp == one {
-                                if handle_trivial {
-                                    acc.add_assign_mixed(base);
-                                }
-                            } else {
-                                let mut exp = exp;
-                                exp.shr(skip);
-                                let exp = exp.as_ref()[0] % (1 << c);
-                                if exp != 0 {
-                                    buckets[(exp - 1) as usize].add_assign_mixed(base);
-                                }
-                            }
-                        }
-                    }
+    let this = pool.map_reduce(bases, |start, base| {
+        let exp = &exponents[start..(start + base.len())];
 
-                    // buckets are filled with the corresponding accumulated value, now sum
-                    let mut running_sum = G::Projective::zero();
-                    for exp in buckets.into_iter().rev() {
-                        running_sum.add_assign(&exp);
-                        acc.add_assign(&running_sum);
+        let mut buckets = vec![<G as CurveAffine>::Projective::zero(); (1 << c) - 1];
+        // Accumulate the result
+        let mut acc = G::Projective::zero();
+        let zero = <G::Engine as ScalarEngine>::Fr::zero().into_repr();
+        let one = <G::Engine as ScalarEngine>::Fr::one().into_repr();
+
+        for (base, &exp) in base.iter().zip(exp.iter()) {
+            if exp != zero {
+                if exp == one {
+                    if handle_trivial {
+                        acc.add_assign_mixed(base);
                     }
-
-                    let mut guard = match this_region_rwlock.lock() {
-                        Ok(guard) => guard,
-                        Err(_) => {
-                            panic!("poisoned!"); 
-                            // poisoned.into_inner()
-                        }
-                    };
-
-                    (*guard).add_assign(&acc);
-                });
-        
+                } else {
+                    let mut exp = exp;
+                    exp.shr(skip);
+                    let exp = exp.as_ref()[0] % (1 << c);
+                    if exp != 0 {
+                        buckets[(exp - 1) as usize].add_assign_mixed(base);
+                    }
+                }
             }
-        });
+        }
 
-        let this_region = Arc::try_unwrap(arc).unwrap();
-        let this_region = this_region.into_inner().unwrap();
+        // buckets are filled with the corresponding accumulated value, now sum
+        let mut running_sum = G::Projective::zero();
+        for exp in buckets.into_iter().rev() {
+            running_sum.add_assign(&exp);
+            acc.add_assign(&running_sum);
+        }
 
-        this_region
-    };
+        acc
+    }, |mut a, b| {
+        a.add_assign(&b);
+        a
+    })?;
 
     skip += c;
 
@@ -291,7 +452,7 @@ fn dense_multiexp_inner<G: CurveAffine>(
     } else {
         // next region is actually higher than this one, so double it enough times
         let mut next_region = dense_multiexp_inner(
-            pool, bases, exponents, skip, c, false).unwrap();
+            pool, bases, exponents, skip, c, false)?;
         for _ in 0..c {
             next_region.double();
         }
@@ -302,6 +463,382 @@ fn dense_multiexp_inner<G: CurveAffine>(
     }
 }
 
+/// This is an error that could occur while running a synchronous multiexp
+#[derive(Debug)]
+pub enum MultiexpError {
+    /// Number of bases is not the same as the number of exponents
+    LengthMismatch {
+        bases: usize,
+        exponents: usize
+    },
+    /// One of the chunks dispatched to the worker pool has panicked
+    WorkerPanicked(WorkerError)
+}
+
+impl Error for MultiexpError {
+    fn description(&self) -> &str {
+        match *self {
+            MultiexpError::LengthMismatch { .. } => "number of bases and exponents is different",
+            MultiexpError::WorkerPanicked(_) => "multiexp worker has panicked"
+        }
+    }
+}
+
+impl fmt::Display for MultiexpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            MultiexpError::LengthMismatch { bases, exponents } => {
+                write!(f, "multiexp over {} bases and {} exponents", bases, exponents)
+            },
+            MultiexpError::WorkerPanicked(ref e) => {
+                write!(f, "multiexp worker chunk {} has panicked: {}", e.chunk, e.message)
+            }
+        }
+    }
+}
+
+/// Perform dense multi-exponentiation and return the result to the caller
+/// instead of panicking if something goes wrong inside of the worker pool.
+pub fn dense_multiexp_sync<G: CurveAffine>(
+    pool: &Worker,
+    bases: & [G],
+    exponents: & [<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr]
+) -> Result<<G as CurveAffine>::Projective, MultiexpError>
+{
+    if exponents.len() != bases.len() {
+        return Err(MultiexpError::LengthMismatch {
+            bases: bases.len(),
+            exponents: exponents.len()
+        });
+    }
+
+    dense_multiexp(pool, bases, exponents).map_err(|e| multiexp_error(e, bases.len(), exponents.len()))
+}
+
+// the dense multiexp catches the panics of its chunks, other than them
+// it only reports a length mismatch
+fn multiexp_error(e: SynthesisError, bases: usize, exponents: usize) -> MultiexpError {
+    match e {
+        SynthesisError::WorkerPanicked(e) => MultiexpError::WorkerPanicked(e),
+        _ => MultiexpError::LengthMismatch { bases, exponents }
+    }
+}
+
+/// Wait for a set of multiexps that were launched on the worker pool to run
+/// concurrently, like A/B/C queries of the prover. The first error is returned
+/// and the rest of the multiexps are dropped, so the ones that have not yet
+/// started are cancelled by the pool.
+pub fn join_all<G, I>(
+    multiexps: I
+) -> Result<Vec<G>, SynthesisError>
+    where I: IntoIterator<Item=Box<dyn Future<Item=G, Error=SynthesisError>>>
+{
+    futures::future::join_all(multiexps).wait()
+}
+
+/// Extracts `c` bits of the scalar starting from the bit `skip`
+fn get_window<R: PrimeFieldRepr>(repr: &R, skip: u32, c: u32) -> u64 {
+    let limbs = repr.as_ref();
+    let limb = (skip / 64) as usize;
+    let shift = skip % 64;
+
+    if limb >= limbs.len() {
+        return 0;
+    }
+
+    let mut window = limbs[limb] >> shift;
+    if shift + c > 64 && limb + 1 < limbs.len() {
+        window |= limbs[limb + 1] << (64 - shift);
+    }
+
+    window & ((1u64 << c) - 1)
+}
+
+/// Bases for a fixed-base multiexp, where for every base `B` the multiples
+/// `B, 2^c * B, 2^(2c) * B, ...` are precomputed once for the window width `c`.
+/// With such a table every window of the scalar can be placed into the same
+/// set of buckets and no doublings are needed at the end, so it pays off
+/// when the same bases (e.g. G1 powers of the SRS) are used for many proofs.
+///
+/// The table takes `num_windows = ceil(NUM_BITS / c)` times more memory than
+/// the bases themselves (for BN256 and `c = 16` it's 16x), so one may want to
+/// precompute only a prefix of the bases and do a plain multiexp for the rest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrecomputedBases<G: CurveAffine> {
+    window: u32,
+    num_windows: u32,
+    // `num_windows` consecutive multiples for every base
+    multiples: Vec<G>
+}
+
+impl<G: CurveAffine> PrecomputedBases<G> {
+    fn num_windows_for(window: u32) -> u32 {
+        let num_bits = <G::Engine as ScalarEngine>::Fr::NUM_BITS;
+
+        num_bits.div_ceil(window)
+    }
+
+    /// Precompute the multiples of `bases` for the window width `window`.
+    /// Pass a slice `&bases[..n]` to precompute only a prefix.
+    pub fn precompute(
+        bases: &[G],
+        window: u32,
+        worker: &Worker
+    ) -> Result<Self, SynthesisError>
+    {
+        if window == 0 || window >= 32 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "window width must be between 1 and 31 bits").into());
+        }
+
+        let num_windows = Self::num_windows_for(window);
+        let mut multiples = vec![G::Projective::zero(); bases.len() * (num_windows as usize)];
+
+        worker.try_scope(bases.len(), |scope, chunk| {
+            for (bases, multiples) in bases.chunks(chunk)
+                                        .zip(multiples.chunks_mut(chunk * (num_windows as usize)))
+            {
+                scope.spawn(move || {
+                    for (base, multiples) in bases.iter().zip(multiples.chunks_mut(num_windows as usize)) {
+                        let mut current = base.into_projective();
+                        for m in multiples.iter_mut() {
+                            *m = current;
+                            for _ in 0..window {
+                                current.double();
+                            }
+                        }
+                    }
+
+                    G::Projective::batch_normalization(multiples);
+                });
+            }
+        })?;
+
+        Ok(PrecomputedBases {
+            window,
+            num_windows,
+            multiples: multiples.into_iter().map(|e| e.into_affine()).collect()
+        })
+    }
+
+    /// Number of bases that have precomputed multiples
+    pub fn len(&self) -> usize {
+        self.multiples.len() / (self.num_windows as usize)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.multiples.is_empty()
+    }
+
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    pub fn write<W: Write>(
+        &self,
+        mut writer: W
+    ) -> io::Result<()>
+    {
+        let len = u32::try_from(self.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "too many bases to serialize")
+        })?;
+
+        writer.write_u32::<BigEndian>(self.window)?;
+        writer.write_u32::<BigEndian>(len)?;
+        for g in &self.multiples {
+            writer.write_all(g.into_uncompressed().as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(
+        mut reader: R,
+        checked: bool
+    ) -> io::Result<Self>
+    {
+        let window = reader.read_u32::<BigEndian>()?;
+        if window == 0 || window >= 32 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid window width"));
+        }
+        let num_windows = Self::num_windows_for(window);
+        let num_bases = reader.read_u32::<BigEndian>()? as usize;
+        let len = num_bases.checked_mul(num_windows as usize).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "too many bases")
+        })?;
+
+        // the length is not trusted before the points are actually there
+        let mut multiples = Vec::with_capacity(std::cmp::min(len, 1 << 16));
+        let mut repr = G::Uncompressed::empty();
+        for _ in 0..len {
+            reader.read_exact(repr.as_mut())?;
+            let g = if checked {
+                repr.into_affine()
+            } else {
+                repr.into_affine_unchecked()
+            }.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            multiples.push(g);
+        }
+
+        Ok(PrecomputedBases {
+            window,
+            num_windows,
+            multiples
+        })
+    }
+}
+
+/// Perform multi-exponentiation over the precomputed bases. Exponents are
+/// matched with the first `exponents.len()` precomputed bases.
+pub fn multiexp_with_precomputation<G: CurveAffine>(
+    precomp: &PrecomputedBases<G>,
+    exponents: &[<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr],
+    worker: &Worker
+) -> Result<<G as CurveAffine>::Projective, SynthesisError>
+{
+    if exponents.len() > precomp.len() {
+        return Err(SynthesisError::AssignmentMissing);
+    }
+
+    let c = precomp.window;
+    let num_windows = precomp.num_windows as usize;
+    let multiples = &precomp.multiples[..(exponents.len() * num_windows)];
+
+    let result = worker.map_reduce(exponents, |start, exponents| {
+        let multiples = &multiples[(start * num_windows)..((start + exponents.len()) * num_windows)];
+
+        // All the windows share the same buckets, as every window
+        // already has its own multiple of the base
+        let mut buckets = vec![<G as CurveAffine>::Projective::zero(); (1 << c) - 1];
+
+        for (exp, multiples) in exponents.iter().zip(multiples.chunks(num_windows)) {
+            if exp.is_zero() {
+                continue;
+            }
+
+            for (i, m) in multiples.iter().enumerate() {
+                let digit = get_window(exp, (i as u32) * c, c);
+                if digit != 0 {
+                    buckets[(digit - 1) as usize].add_assign_mixed(m);
+                }
+            }
+        }
+
+        let mut acc = G::Projective::zero();
+        let mut running_sum = G::Projective::zero();
+        for exp in buckets.into_iter().rev() {
+            running_sum.add_assign(&exp);
+            acc.add_assign(&running_sum);
+        }
+
+        acc
+    }, |mut a, b| {
+        a.add_assign(&b);
+        a
+    })?;
+
+    Ok(result)
+}
+
+/// Signed digit of `c` bits of the scalar starting from the bit `skip`,
+/// where `carry` is the carry out of the window below it and is updated for
+/// the window above. Every digit is in `[-2^(c - 1), 2^(c - 1))`, so a
+/// window needs only `2^(c - 1)` buckets, and a negative digit adds
+/// the negated base.
+fn signed_digit<R: PrimeFieldRepr>(repr: &R, skip: u32, c: u32, carry: &mut bool) -> i64 {
+    let half = 1i64 << (c - 1);
+    let mut digit = get_window(repr, skip, c) as i64 + (*carry as i64);
+
+    *carry = digit >= half;
+    if *carry {
+        digit -= half << 1;
+    }
+
+    digit
+}
+
+/// Perform multi-exponentiation with signed digits of `window` bits.
+/// Every chunk of the worker fills the buckets of all the windows for its
+/// bases, then the window sums of the chunks are added together and
+/// combined from the highest window down.
+pub fn windowed_multiexp<G: CurveAffine>(
+    bases: &[G],
+    exponents: &[<<G::Engine as ScalarEngine>::Fr as PrimeField>::Repr],
+    window: u32,
+    worker: &Worker
+) -> Result<<G as CurveAffine>::Projective, SynthesisError>
+{
+    // with a single bit the only digits are -1 and 0, so a carry never ends,
+    // and the buckets of wider windows do not pay off
+    if !(2..=20).contains(&window) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "window must be between 2 and 20 bits").into());
+    }
+
+    if exponents.len() != bases.len() {
+        return Err(SynthesisError::LengthMismatch {
+            expected: bases.len(),
+            got: exponents.len()
+        });
+    }
+
+    let c = window;
+    // one more digit than the windows of the scalar, for the carry of the top window
+    let num_digits = <G::Engine as ScalarEngine>::Fr::NUM_BITS.div_ceil(c) + 1;
+
+    let window_sums = worker.map_reduce(bases, |start, bases| {
+        let exponents = &exponents[start..(start + bases.len())];
+        // the digits are recoded window by window, only the carries are kept
+        let mut carries = vec![false; bases.len()];
+
+        let mut buckets = vec![<G as CurveAffine>::Projective::zero(); 1 << (c - 1)];
+
+        let window_sums = (0..num_digits).map(|i| {
+            for bucket in buckets.iter_mut() {
+                *bucket = G::Projective::zero();
+            }
+
+            for ((base, exp), carry) in bases.iter().zip(exponents.iter()).zip(carries.iter_mut()) {
+                let digit = signed_digit(exp, i * c, c, carry);
+                if digit > 0 {
+                    buckets[(digit - 1) as usize].add_assign_mixed(base);
+                } else if digit < 0 {
+                    let mut base = *base;
+                    base.negate();
+                    buckets[(-digit - 1) as usize].add_assign_mixed(&base);
+                }
+            }
+
+            let mut acc = G::Projective::zero();
+            let mut running_sum = G::Projective::zero();
+            for bucket in buckets.iter().rev() {
+                running_sum.add_assign(bucket);
+                acc.add_assign(&running_sum);
+            }
+
+            acc
+        }).collect::<Vec<_>>();
+
+        debug_assert!(carries.iter().all(|carry| !carry), "the digits must cover the carry of the top window");
+
+        window_sums
+    }, |mut a, b| {
+        for (a, b) in a.iter_mut().zip(b.iter()) {
+            a.add_assign(b);
+        }
+        a
+    })?;
+
+    let mut result = G::Projective::zero();
+    for sum in window_sums.iter().rev() {
+        for _ in 0..c {
+            result.double();
+        }
+        result.add_assign(sum);
+    }
+
+    Ok(result)
+}
+
 
 
 #[test]
@@ -415,4 +952,434 @@ fn test_dense_multiexp() {
     println!("{} ns for sparse for {} samples", duration_ns, SAMPLES);
 
     assert_eq!(dense, sparse);
-}
\ No newline at end of file
+}
+#[test]
+fn test_multiexp_with_precomputation() {
+    use rand::{XorShiftRng, SeedableRng, Rand};
+    use crate::pairing::bn256::Bn256;
+
+    const SAMPLES: usize = 1 << 10;
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let mut v = (0..SAMPLES).map(|_| <Bn256 as ScalarEngine>::Fr::rand(rng).into_repr()).collect::<Vec<_>>();
+    let g = (0..SAMPLES).map(|_| <Bn256 as Engine>::G1::rand(rng).into_affine()).collect::<Vec<_>>();
+
+    // trivial exponents should also be handled
+    v[0] = <Bn256 as ScalarEngine>::Fr::zero().into_repr();
+    v[1] = <Bn256 as ScalarEngine>::Fr::one().into_repr();
+
+    let pool = Worker::new();
+
+    let dense = dense_multiexp(&pool, &g, &v).unwrap();
+
+    for window in [1, 3, 7, 8, 13, 16] {
+        let precomp = PrecomputedBases::precompute(&g, window, &pool).unwrap();
+        assert_eq!(precomp.len(), SAMPLES);
+
+        let fast = multiexp_with_precomputation(&precomp, &v, &pool).unwrap();
+        assert_eq!(dense, fast);
+    }
+
+    // only a prefix of the bases is precomputed, the rest goes through a plain multiexp
+    let prefix = SAMPLES / 4;
+    let precomp = PrecomputedBases::precompute(&g[..prefix], 8, &pool).unwrap();
+    assert!(multiexp_with_precomputation(&precomp, &v, &pool).is_err());
+
+    let mut split = multiexp_with_precomputation(&precomp, &v[..prefix], &pool).unwrap();
+    split.add_assign(&dense_multiexp(&pool, &g[prefix..], &v[prefix..]).unwrap());
+    assert_eq!(dense, split);
+
+    for &window in &[0, 32] {
+        assert!(PrecomputedBases::precompute(&g, window, &pool).is_err());
+    }
+}
+
+#[test]
+fn test_precomputed_bases_serialization() {
+    use rand::{XorShiftRng, SeedableRng, Rand};
+    use crate::pairing::bls12_381::Bls12;
+
+    const SAMPLES: usize = 1 << 6;
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let g = (0..SAMPLES).map(|_| <Bls12 as Engine>::G1::rand(rng).into_affine()).collect::<Vec<_>>();
+
+    let pool = Worker::new();
+    let precomp = PrecomputedBases::precompute(&g, 11, &pool).unwrap();
+
+    let mut v = vec![];
+    precomp.write(&mut v).unwrap();
+
+    let de = PrecomputedBases::<<Bls12 as Engine>::G1Affine>::read(&v[..], true).unwrap();
+    assert!(precomp == de);
+
+    let de = PrecomputedBases::<<Bls12 as Engine>::G1Affine>::read(&v[..], false).unwrap();
+    assert!(precomp == de);
+
+    assert!(PrecomputedBases::<<Bls12 as Engine>::G1Affine>::read(&v[..(v.len() - 1)], true).is_err());
+
+    // a header with too many bases fails at the missing points, before
+    // anything of its size is allocated
+    let mut huge = vec![0, 0, 0, 1, 0xff, 0xff, 0xff, 0xff];
+    huge.extend_from_slice(&v[8..]);
+    match PrecomputedBases::<<Bls12 as Engine>::G1Affine>::read(&huge[..], false) {
+        Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {},
+        _ => panic!("expected an unexpected end of the file")
+    }
+}
+
+#[test]
+fn test_precomputed_speed_with_bn256() {
+    use rand::{self, Rand};
+    use crate::pairing::bn256::Bn256;
+
+    const SAMPLES: usize = 1 << 16;
+    const PROOFS: usize = 4;
+
+    let rng = &mut rand::thread_rng();
+    let g = (0..SAMPLES).map(|_| <Bn256 as Engine>::G1::rand(rng).into_affine()).collect::<Vec<_>>();
+
+    let pool = Worker::new();
+
+    let start = std::time::Instant::now();
+    let precomp = PrecomputedBases::precompute(&g, 16, &pool).unwrap();
+    println!("{} ns to precompute {} bases", start.elapsed().as_nanos(), SAMPLES);
+
+    let mut plain_ns = 0;
+    let mut precomputed_ns = 0;
+
+    for _ in 0..PROOFS {
+        let v = (0..SAMPLES).map(|_| <Bn256 as ScalarEngine>::Fr::rand(rng).into_repr()).collect::<Vec<_>>();
+
+        let start = std::time::Instant::now();
+        let plain = dense_multiexp(&pool, &g, &v).unwrap();
+        plain_ns += start.elapsed().as_nanos();
+
+        let start = std::time::Instant::now();
+        let fast = multiexp_with_precomputation(&precomp, &v, &pool).unwrap();
+        precomputed_ns += start.elapsed().as_nanos();
+
+        assert_eq!(plain, fast);
+    }
+
+    println!("{} ns per plain multiexp for {} samples", plain_ns / (PROOFS as u128), SAMPLES);
+    println!("{} ns per precomputed multiexp for {} samples", precomputed_ns / (PROOFS as u128), SAMPLES);
+}
+
+#[test]
+fn test_dense_multiexp_sync_length_mismatch() {
+    use rand::{XorShiftRng, SeedableRng, Rand};
+    use crate::pairing::bls12_381::Bls12;
+
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let v = (0..8).map(|_| <Bls12 as ScalarEngine>::Fr::rand(rng).into_repr()).collect::<Vec<_>>();
+    let g = (0..7).map(|_| <Bls12 as Engine>::G1::rand(rng).into_affine()).collect::<Vec<_>>();
+
+    let pool = Worker::new();
+
+    match dense_multiexp_sync(&pool, &g, &v) {
+        Err(MultiexpError::LengthMismatch { bases: 7, exponents: 8 }) => {},
+        _ => panic!("length mismatch is expected")
+    }
+
+    let expected = dense_multiexp(&pool, &g, &v[..7]).unwrap();
+    assert_eq!(dense_multiexp_sync(&pool, &g, &v[..7]).unwrap(), expected);
+}
+
+#[cfg(feature = "multicore")]
+#[test]
+fn test_dense_multiexp_sync_reports_panicked_chunk() {
+    let pool = Worker::new_with_cpus(8);
+    let data = vec![0u8; 8];
+
+    let result = pool.map_reduce(&data, |start, _| {
+        if start == 3 {
+            panic!("chunk 3 is broken");
+        }
+    }, |_, _| ()).map_err(SynthesisError::from);
+
+    match result.map_err(|e| multiexp_error(e, 8, 8)) {
+        Err(MultiexpError::WorkerPanicked(ref e)) if e.chunk == 3 && e.message == "chunk 3 is broken" => {},
+        _ => panic!("the panic of chunk 3 is expected")
+    }
+}
+
+#[test]
+fn test_join_all_propagates_errors() {
+    use rand::{XorShiftRng, SeedableRng, Rand};
+    use crate::pairing::bls12_381::Bls12;
+
+    const SAMPLES: usize = 1 << 10;
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let v = Arc::new((0..SAMPLES).map(|_| <Bls12 as ScalarEngine>::Fr::rand(rng).into_repr()).collect::<Vec<_>>());
+    let g = Arc::new((0..SAMPLES).map(|_| <Bls12 as Engine>::G1::rand(rng).into_affine()).collect::<Vec<_>>());
+
+    let mut bad = (*g).clone();
+    bad[SAMPLES / 2] = <Bls12 as Engine>::G1Affine::zero();
+    let bad = Arc::new(bad);
+
+    let pool = Worker::new();
+
+    let expected = dense_multiexp(&pool, &g, &v).unwrap();
+
+    let good = multiexp(&pool, (g.clone(), 0), FullDensity, v.clone());
+    let broken = multiexp(&pool, (bad, 0), FullDensity, v.clone());
+    let other = multiexp(&pool, (g.clone(), 0), FullDensity, v.clone());
+
+    match join_all(vec![good, broken, other]) {
+        Err(SynthesisError::UnexpectedIdentity) => {},
+        _ => panic!("identity in the bases is expected to be reported")
+    }
+
+    // the pool is still usable afterwards
+    let results = join_all(vec![
+        multiexp(&pool, (g.clone(), 0), FullDensity, v.clone()),
+        multiexp(&pool, (g, 0), FullDensity, v)
+    ]).unwrap();
+
+    assert_eq!(results, vec![expected, expected]);
+}
+
+#[test]
+fn test_multiexp_reports_panicked_chunk() {
+    use rand::{XorShiftRng, SeedableRng, Rand};
+    use crate::pairing::bls12_381::{Bls12, G1Affine};
+
+    // panics when the base at `position` is added
+    #[derive(Clone)]
+    struct Panicking(Arc<Vec<G1Affine>>, usize, usize);
+
+    impl SourceBuilder<G1Affine> for Panicking {
+        type Source = Self;
+
+        fn new(self) -> Self {
+            self
+        }
+    }
+
+    impl Source<G1Affine> for Panicking {
+        fn add_assign_mixed(&mut self, to: &mut <G1Affine as CurveAffine>::Projective) -> Result<(), SynthesisError> {
+            assert!(self.1 != self.2, "broken base");
+            to.add_assign_mixed(&self.0[self.1]);
+            self.1 += 1;
+
+            Ok(())
+        }
+
+        fn skip(&mut self, amt: usize) -> Result<(), SynthesisError> {
+            self.1 += amt;
+
+            Ok(())
+        }
+    }
+
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    // few enough exponents for windows of 3 bits
+    let mut v = (0..16).map(|_| <Bls12 as ScalarEngine>::Fr::rand(rng).into_repr()).collect::<Vec<_>>();
+    v[5] = <Bls12 as ScalarEngine>::Fr::one().into_repr();
+    // only the lowest window is not zero
+    v[8] = <Bls12 as ScalarEngine>::Fr::from_str("5").unwrap().into_repr();
+    let g = Arc::new((0..16).map(|_| <Bls12 as Engine>::G1::rand(rng).into_affine()).collect::<Vec<_>>());
+
+    let pool = Worker::new();
+
+    let num_regions = <Bls12 as ScalarEngine>::Fr::NUM_BITS.div_ceil(3) as usize;
+
+    for &(position, chunk) in &[(8, 0), (5, num_regions)] {
+        match multiexp(&pool, Panicking(g.clone(), 0, position), FullDensity, Arc::new(v.clone())).wait() {
+            Err(SynthesisError::WorkerPanicked(ref e)) if e.chunk == chunk && e.message == "broken base" => {},
+            _ => panic!("the panic of chunk {} is expected", chunk)
+        }
+    }
+}
+
+#[test]
+fn test_windowed_multiexp() {
+    use rand::{XorShiftRng, SeedableRng, Rand};
+    use crate::pairing::bn256::Bn256;
+
+    const SAMPLES: usize = 300;
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let mut v = (0..SAMPLES).map(|_| <Bn256 as ScalarEngine>::Fr::rand(rng).into_repr()).collect::<Vec<_>>();
+    let g = (0..SAMPLES).map(|_| <Bn256 as Engine>::G1::rand(rng).into_affine()).collect::<Vec<_>>();
+
+    // the largest scalar has a carry out of the top window
+    v[0] = <Bn256 as ScalarEngine>::Fr::zero().into_repr();
+    v[1] = {
+        let mut minus_one = <Bn256 as ScalarEngine>::Fr::one();
+        minus_one.negate();
+        minus_one.into_repr()
+    };
+
+    let mut naive = <Bn256 as Engine>::G1::zero();
+    for (base, exp) in g.iter().zip(v.iter()) {
+        naive.add_assign(&base.mul(*exp));
+    }
+
+    let pool = Worker::new();
+
+    for &window in &[2, 3, 5, 8, 13, 16, 20] {
+        let windowed = windowed_multiexp(&g, &v, window, &pool).unwrap();
+        assert_eq!(windowed, naive, "window of {} bits", window);
+    }
+
+    match windowed_multiexp(&g[1..], &v, 4, &pool) {
+        Err(SynthesisError::LengthMismatch { expected, got }) if expected == g.len() - 1 && got == v.len() => {},
+        _ => panic!("expected a length mismatch")
+    }
+
+    for &window in &[0, 1, 21] {
+        match windowed_multiexp(&g, &v, window, &pool) {
+            Err(SynthesisError::IoError(ref e)) if e.kind() == io::ErrorKind::InvalidInput => {},
+            _ => panic!("expected an invalid window of {} bits", window)
+        }
+    }
+}
+
+#[test]
+fn test_windowed_multiexp_with_bls12() {
+    use rand::{XorShiftRng, SeedableRng, Rand};
+    use crate::pairing::bls12_381::Bls12;
+
+    const SAMPLES: usize = 300;
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let mut v = (0..SAMPLES).map(|_| <Bls12 as ScalarEngine>::Fr::rand(rng).into_repr()).collect::<Vec<_>>();
+    let g = (0..SAMPLES).map(|_| <Bls12 as Engine>::G1::rand(rng).into_affine()).collect::<Vec<_>>();
+
+    // 255 bits leave one bit in the top window of 2 bits, and the top
+    // bits 0111 of -1 carry into it
+    v[0] = {
+        let mut minus_one = <Bls12 as ScalarEngine>::Fr::one();
+        minus_one.negate();
+        minus_one.into_repr()
+    };
+
+    let mut naive = <Bls12 as Engine>::G1::zero();
+    for (base, exp) in g.iter().zip(v.iter()) {
+        naive.add_assign(&base.mul(*exp));
+    }
+
+    let pool = Worker::new();
+
+    for &window in &[2, 4, 8] {
+        let windowed = windowed_multiexp(&g, &v, window, &pool).unwrap();
+        assert_eq!(windowed, naive, "window of {} bits", window);
+
+        let single = windowed_multiexp(&g[..1], &v[..1], window, &pool).unwrap();
+        assert_eq!(single, g[0].mul(v[0]), "-1 with a window of {} bits", window);
+    }
+}
+
+#[cfg(test)]
+fn boolean_heavy_exponents<R: rand::Rng>(
+    rng: &mut R,
+    samples: usize,
+    booleans: f64
+) -> Vec<<<crate::pairing::bn256::Bn256 as ScalarEngine>::Fr as PrimeField>::Repr>
+{
+    use crate::pairing::bn256::Fr;
+
+    (0..samples).map(|_| {
+        if rng.gen::<f64>() < booleans {
+            if rng.gen() { Fr::one() } else { Fr::zero() }
+        } else {
+            rng.gen::<Fr>()
+        }.into_repr()
+    }).collect()
+}
+
+#[test]
+fn test_multiexp_partition() {
+    use rand::{XorShiftRng, SeedableRng, Rand, Rng};
+    use crate::pairing::bn256::Bn256;
+
+    const SAMPLES: usize = 1 << 10;
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    // more chunks than the cores of a small machine
+    let pool = Worker::new_with_cpus(8);
+    let g = Arc::new((0..SAMPLES).map(|_| <Bn256 as Engine>::G1::rand(rng).into_affine()).collect::<Vec<_>>());
+
+    for &booleans in &[0.0, 0.5, 1.0] {
+        let v = Arc::new(boolean_heavy_exponents(rng, SAMPLES, booleans));
+
+        let mut density = DensityTracker::new();
+        for i in 0..SAMPLES {
+            density.add_element();
+            if rng.gen() {
+                density.inc(i);
+            }
+        }
+        let density = Arc::new(density);
+
+        let mut naive = <Bn256 as Engine>::G1::zero();
+        let mut naive_sparse = <Bn256 as Engine>::G1::zero();
+        let mut bases = g.iter();
+        for ((base, exp), d) in g.iter().zip(v.iter()).zip(density.iter()) {
+            naive.add_assign(&base.mul(*exp));
+            if d {
+                naive_sparse.add_assign(&bases.next().unwrap().mul(*exp));
+            }
+        }
+
+        let full = multiexp(&pool, (g.clone(), 0), FullDensity, v.clone()).wait().unwrap();
+        let unsplit = multiexp_with_partition(&pool, (g.clone(), 0), FullDensity, v.clone(), false).wait().unwrap();
+        assert_eq!(full, naive, "{} booleans", booleans);
+        assert_eq!(unsplit, naive, "{} booleans", booleans);
+
+        let sparse = multiexp(&pool, (g.clone(), 0), density.clone(), v.clone()).wait().unwrap();
+        let unsplit = multiexp_with_partition(&pool, (g.clone(), 0), density.clone(), v.clone(), false).wait().unwrap();
+        assert_eq!(sparse, naive_sparse, "{} booleans", booleans);
+        assert_eq!(unsplit, naive_sparse, "{} booleans", booleans);
+
+        let mut indexed = (*density).clone();
+        indexed.build_index();
+        let sparse = multiexp(&pool, (g.clone(), 0), Arc::new(indexed), v.clone()).wait().unwrap();
+        assert_eq!(sparse, naive_sparse, "{} booleans with an index", booleans);
+    }
+
+    // the bases of trailing zeros are still required
+    let mut v = boolean_heavy_exponents(rng, SAMPLES, 0.5);
+    v[SAMPLES - 1] = <Bn256 as ScalarEngine>::Fr::zero().into_repr();
+    let short = Arc::new(g[..(SAMPLES - 1)].to_vec());
+    assert!(multiexp(&pool, (short, 0), FullDensity, Arc::new(v.clone())).wait().is_err());
+
+    // a density map that does not cover every exponent
+    let mut density = DensityTracker::new();
+    density.add_element();
+    density.inc(0);
+    match multiexp(&pool, (g.clone(), 0), Arc::new(density), Arc::new(v)).wait() {
+        Err(SynthesisError::LengthMismatch { expected: 1, got: SAMPLES }) => {},
+        _ => panic!("expected a length mismatch")
+    }
+}
+
+#[test]
+fn test_multiexp_partition_speed_with_booleans() {
+    use rand::{XorShiftRng, SeedableRng, Rand};
+    use crate::pairing::bn256::Bn256;
+
+    const SAMPLES: usize = 1 << 16;
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let v = Arc::new(boolean_heavy_exponents(rng, SAMPLES, 0.9));
+    let g = Arc::new((0..SAMPLES).map(|_| <Bn256 as Engine>::G1::rand(rng).into_affine()).collect::<Vec<_>>());
+
+    let pool = Worker::new();
+
+    let start = std::time::Instant::now();
+    let unsplit = multiexp_with_partition(&pool, (g.clone(), 0), FullDensity, v.clone(), false).wait().unwrap();
+    let unsplit_time = start.elapsed();
+
+    let start = std::time::Instant::now();
+    let split = multiexp(&pool, (g, 0), FullDensity, v).wait().unwrap();
+    let split_time = start.elapsed();
+
+    assert_eq!(split, unsplit);
+    println!("{} samples with 90% booleans: {:?} with the partition, {:?} without", SAMPLES, split_time, unsplit_time);
+}
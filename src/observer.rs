@@ -0,0 +1,50 @@
+//! This module contains a `ProvingObserver` that receives the
+//! timings and progress of the prover phases, so long-running
+//! proofs can report what they are doing.
+
+use std::time::Duration;
+
+/// Name of the phase where the circuit is synthesized
+pub const PHASE_SYNTHESIS: &str = "synthesis";
+/// Name of the phase where the H polynomial is computed (mostly FFTs)
+pub const PHASE_FFT: &str = "fft";
+/// Name of the phase where the multiexps are computed
+pub const PHASE_MULTIEXP: &str = "multiexp";
+
+pub trait ProvingObserver: Send + Sync {
+    /// Called when the phase `name` has started.
+    fn phase_started(&self, _name: &str) {}
+
+    /// Called when the phase `name` has finished after running for `duration`.
+    fn phase_finished(&self, _name: &str, _duration: Duration) {}
+
+    /// Called when `done` out of `total` chunks of the phase `name` have
+    /// finished on the worker threads. `total` is the number of chunks
+    /// started so far, so it can still grow while the phase runs.
+    fn progress(&self, _name: &str, _done: usize, _total: usize) {}
+}
+
+/// Observer that ignores everything.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NullObserver;
+
+impl ProvingObserver for NullObserver {}
+
+/// Observer that prints every event to stderr with `elog!`, like the
+/// rest of the crate does, as there is no dependency on the `log` crate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoggingObserver;
+
+impl ProvingObserver for LoggingObserver {
+    fn phase_started(&self, name: &str) {
+        elog!("prover phase {} has started", name);
+    }
+
+    fn phase_finished(&self, name: &str, duration: Duration) {
+        elog!("prover phase {} has finished in {} seconds", name, duration.as_secs_f64());
+    }
+
+    fn progress(&self, name: &str, done: usize, total: usize) {
+        elog!("prover phase {}: {} of {} done", name, done, total);
+    }
+}
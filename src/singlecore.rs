@@ -3,6 +3,13 @@
 extern crate futures;
 
 use std::marker::PhantomData;
+use std::ops::Range;
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::panic::{self, AssertUnwindSafe};
+
+pub use super::worker_error::WorkerError;
 
 use self::futures::{Future, IntoFuture, Poll};
 use self::futures::future::{result, FutureResult};
@@ -10,15 +17,22 @@ use self::futures::future::{result, FutureResult};
 #[derive(Clone)]
 pub struct Worker {
     cpus: usize,
+    chunks: Option<Arc<AtomicUsize>>
+}
+
+impl Default for Worker {
+    fn default() -> Worker {
+        Self::new()
+    }
 }
 
 impl Worker {
-    // We don't expose this outside the library so that
-    // all `Worker` instances have the same number of
-    // CPUs configured.
-    pub(crate) fn new_with_cpus(_cpus: usize) -> Worker {
+    /// The number of threads is ignored, everything runs on
+    /// the calling thread.
+    pub fn new_with_cpus(_cpus: usize) -> Worker {
         Worker {
             cpus: 1,
+            chunks: None
         }
     }
 
@@ -26,6 +40,16 @@ impl Worker {
         Self::new_with_cpus(1)
     }
 
+    pub fn global() -> &'static Worker {
+        static GLOBAL: Worker = Worker { cpus: 1, chunks: None };
+
+        &GLOBAL
+    }
+
+    pub fn num_cpus(&self) -> usize {
+        self.cpus
+    }
+
     pub fn log_num_cpus(&self) -> u32 {
         0u32
     }
@@ -39,6 +63,10 @@ impl Worker {
               R::Item: Send + 'static,
               R::Error: Send + 'static
     {
+        if let Some(chunks) = &self.chunks {
+            chunks.fetch_add(1, Ordering::SeqCst);
+        }
+
         let future = f().into_future();
 
         WorkerFuture {
@@ -46,6 +74,32 @@ impl Worker {
         }
     }
 
+    /// Runs `f` and calls `progress(done, total)` for all the chunks of
+    /// `compute` and `try_scope` afterwards, as there is no thread to
+    /// report them while `f` is running.
+    pub fn observe_chunks<F, R, P>(
+        &self,
+        progress: P,
+        f: F
+    ) -> R
+        where F: FnOnce(&Worker) -> R,
+              P: Fn(usize, usize) + Sync
+    {
+        let worker = Worker {
+            cpus: self.cpus,
+            chunks: Some(Arc::new(AtomicUsize::new(0)))
+        };
+
+        let result = f(&worker);
+
+        let total = worker.chunks.as_ref().map_or(0, |chunks| chunks.load(Ordering::SeqCst));
+        for done in 1..=total {
+            progress(done, total);
+        }
+
+        result
+    }
+
     pub fn scope<'a, F, R>(
         &self,
         elements: usize,
@@ -61,6 +115,104 @@ impl Worker {
 
         f(&scope, chunk_size)
     }
+
+    pub fn try_scope<'a, F, R>(
+        &self,
+        elements: usize,
+        f: F
+    ) -> Result<R, WorkerError>
+        where F: FnOnce(&ChunkScope<'_, 'a>, usize) -> R
+    {
+        let scope = ChunkScope {
+            next_chunk: Cell::new(0),
+            first_error: RefCell::new(None),
+            _marker: PhantomData
+        };
+
+        let result = f(&scope, elements);
+
+        if let Some(chunks) = &self.chunks {
+            chunks.fetch_add(scope.next_chunk.get(), Ordering::SeqCst);
+        }
+
+        match scope.first_error.into_inner() {
+            Some(e) => Err(e),
+            None => Ok(result)
+        }
+    }
+
+    pub fn map_reduce<T, R, M, Rd>(
+        &self,
+        data: &[T],
+        map: M,
+        _reduce: Rd
+    ) -> Result<R, WorkerError>
+        where T: Sync,
+              R: Send,
+              M: Fn(usize, &[T]) -> R + Sync,
+              Rd: Fn(R, R) -> R
+    {
+        let mut result = None;
+
+        self.try_scope(data.len(), |scope, _| {
+            let result = &mut result;
+            let map = &map;
+
+            scope.spawn(move || {
+                *result = Some(map(0, data));
+            });
+        })?;
+
+        Ok(result.expect("the only chunk has completed"))
+    }
+
+    pub fn map_in_place<T, F>(
+        &self,
+        data: &mut [T],
+        f: F
+    ) -> Result<(), WorkerError>
+        where T: Send,
+              F: Fn(usize, &mut [T]) + Sync
+    {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        self.try_scope(data.len(), |scope, _| {
+            let f = &f;
+
+            scope.spawn(move || {
+                f(0, data);
+            });
+        })
+    }
+
+    pub fn get_ranges(&self, elements: usize) -> Vec<Range<usize>> {
+        if elements == 0 {
+            vec![]
+        } else {
+            vec![0..elements]
+        }
+    }
+
+    pub fn scope_with_limit<'a, F, R>(
+        &self,
+        _max_parallelism: usize,
+        elements: usize,
+        f: F
+    ) -> R
+        where F: FnOnce(&Scope<'a>, usize) -> R
+    {
+        self.scope(elements, f)
+    }
+
+    pub fn get_chunk_size(&self, elements: usize) -> usize {
+        elements
+    }
+
+    pub fn get_chunk_size_with_limit(&self, _max_parallelism: usize, elements: usize) -> usize {
+        elements
+    }
 }
 #[derive(Clone)]
 pub struct Scope<'a> {
@@ -78,6 +230,31 @@ impl<'a> Scope<'a> {
     }
 }
 
+pub struct ChunkScope<'s, 'a> {
+    next_chunk: Cell<usize>,
+    first_error: RefCell<Option<WorkerError>>,
+    _marker: PhantomData<(&'s usize, &'a usize)>
+}
+
+impl<'s, 'a> ChunkScope<'s, 'a> {
+    pub fn spawn<F>(&self, f: F)
+        where F: FnOnce() + Send + 'a
+    {
+        let chunk = self.next_chunk.get();
+        self.next_chunk.set(chunk + 1);
+
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+            let mut first_error = self.first_error.borrow_mut();
+            if first_error.is_none() {
+                *first_error = Some(WorkerError {
+                    chunk,
+                    message: crate::panic_message(payload)
+                });
+            }
+        }
+    }
+}
+
 pub struct WorkerFuture<T, E> {
     future: FutureResult<T, E>
 }
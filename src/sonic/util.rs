@@ -509,13 +509,13 @@ pub fn multiply_polynomials<E: Engine>(a: Vec<E::Fr>, b: Vec<E::Fr>) -> Vec<E::F
     let scalars_b: Vec<Scalar<E>> = b.into_iter().map(|e| Scalar::<E>(e)).collect();
     let mut domain_b = EvaluationDomain::from_coeffs_into_sized(scalars_b, result_len).unwrap();
 
-    domain_a.fft(&worker);
-    domain_b.fft(&worker);
+    domain_a.fft(&worker).expect("worker must not panic");
+    domain_b.fft(&worker).expect("worker must not panic");
 
-    domain_a.mul_assign(&worker, &domain_b);
+    domain_a.mul_assign(&worker, &domain_b).expect("worker must not panic");
     drop(domain_b);
 
-    domain_a.ifft(&worker);
+    domain_a.ifft(&worker).expect("worker must not panic");
 
     let mut mul_result: Vec<E::Fr> = domain_a.into_coeffs().iter().map(|e| e.0).collect();
 
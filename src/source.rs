@@ -11,9 +11,12 @@ use crate::pairing::ff::{
     ScalarEngine};
 
 use std::sync::Arc;
-use std::io;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
 use bit_vec::{self, BitVec};
+use byteorder::{BigEndian, WriteBytesExt, ReadBytesExt};
 use std::iter;
+use std::ops::Range;
 
 use super::SynthesisError;
 
@@ -31,6 +34,21 @@ pub trait Source<G: CurveAffine> {
 
     /// Skips `amt` elements from the source, avoiding deserialization.
     fn skip(&mut self, amt: usize) -> Result<(), SynthesisError>;
+
+    /// Reads the next `amt` elements, e.g. to hand them to an external
+    /// backend. Fails if any of them is the point at infinity. The default
+    /// implementation normalizes the bases one by one, so sources that keep
+    /// the bases in affine form should override it.
+    fn read_bases(&mut self, amt: usize) -> Result<Vec<G>, SynthesisError> {
+        let mut bases = Vec::with_capacity(amt);
+        for _ in 0..amt {
+            let mut base = G::Projective::zero();
+            self.add_assign_mixed(&mut base)?;
+            bases.push(base.into_affine());
+        }
+
+        Ok(bases)
+    }
 }
 
 impl<G: CurveAffine> SourceBuilder<G> for (Arc<Vec<G>>, usize) {
@@ -67,6 +85,20 @@ impl<G: CurveAffine> Source<G> for (Arc<Vec<G>>, usize) {
 
         Ok(())
     }
+
+    fn read_bases(&mut self, amt: usize) -> Result<Vec<G>, SynthesisError> {
+        let bases = self.0.get(self.1..).and_then(|b| b.get(..amt)).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "expected more bases when reading from source")
+        })?;
+
+        if bases.iter().any(|b| b.is_zero()) {
+            return Err(SynthesisError::UnexpectedIdentity)
+        }
+
+        self.1 += amt;
+
+        Ok(bases.to_vec())
+    }
 }
 
 pub trait QueryDensity {
@@ -75,6 +107,16 @@ pub trait QueryDensity {
 
     fn iter(self) -> Self::Iter;
     fn get_query_size(self) -> Option<usize>;
+
+    /// Whether the element `idx` has a base.
+    fn has_base(self, idx: usize) -> bool;
+
+    /// Number of bases of the elements before `idx`.
+    fn rank(self, idx: usize) -> usize;
+
+    /// Element of the base `k`, counting from zero, or `None` if there
+    /// are `k` bases or fewer.
+    fn select(self, k: usize) -> Option<usize>;
 }
 
 #[derive(Clone)]
@@ -96,14 +138,37 @@ impl<'a> QueryDensity for &'a FullDensity {
     fn get_query_size(self) -> Option<usize> {
         None
     }
+
+    fn has_base(self, _: usize) -> bool {
+        true
+    }
+
+    fn rank(self, idx: usize) -> usize {
+        idx
+    }
+
+    fn select(self, k: usize) -> Option<usize> {
+        Some(k)
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug)]
 pub struct DensityTracker {
     bv: BitVec,
-    total_density: usize
+    total_density: usize,
+    // number of set bits before every block of `bv`, and after the last one;
+    // empty until `build_index` and after every change of `bv`
+    ranks: Vec<usize>
 }
 
+impl PartialEq for DensityTracker {
+    fn eq(&self, other: &DensityTracker) -> bool {
+        self.bv == other.bv
+    }
+}
+
+impl Eq for DensityTracker {}
+
 impl<'a> QueryDensity for &'a DensityTracker {
     type Iter = bit_vec::Iter<'a>;
 
@@ -114,28 +179,263 @@ impl<'a> QueryDensity for &'a DensityTracker {
     fn get_query_size(self) -> Option<usize> {
         Some(self.bv.len())
     }
+
+    fn has_base(self, idx: usize) -> bool {
+        self.bv.get(idx).unwrap_or(false)
+    }
+
+    fn rank(self, idx: usize) -> usize {
+        DensityTracker::rank(self, idx)
+    }
+
+    fn select(self, k: usize) -> Option<usize> {
+        DensityTracker::select(self, k)
+    }
+}
+
+impl Default for DensityTracker {
+    fn default() -> DensityTracker {
+        Self::new()
+    }
 }
 
 impl DensityTracker {
     pub fn new() -> DensityTracker {
         DensityTracker {
             bv: BitVec::new(),
-            total_density: 0
+            total_density: 0,
+            ranks: vec![]
         }
     }
 
     pub fn add_element(&mut self) {
         self.bv.push(false);
+        self.ranks.clear();
     }
 
     pub fn inc(&mut self, idx: usize) {
         if !self.bv.get(idx).unwrap() {
             self.bv.set(idx, true);
             self.total_density += 1;
+            self.ranks.clear();
         }
     }
 
     pub fn get_total_density(&self) -> usize {
         self.total_density
     }
-}
\ No newline at end of file
+
+    /// Counts the set bits before every block of the map, so `query_range`
+    /// and `rank` take constant time, and `select` logarithmic time, until
+    /// the map changes again.
+    pub fn build_index(&mut self) {
+        if !self.ranks.is_empty() {
+            // the map has not changed since the last time
+            return;
+        }
+
+        let mut ranks = Vec::with_capacity(self.bv.storage().len() + 1);
+        let mut rank = 0;
+        ranks.push(rank);
+        for block in self.bv.storage() {
+            rank += block.count_ones() as usize;
+            ranks.push(rank);
+        }
+
+        self.ranks = ranks;
+    }
+
+    /// Number of set bits in the `range`, i.e. the number of bases of the
+    /// query for the elements in it. Without an index the blocks before the
+    /// ends of the range are counted one by one.
+    pub fn query_range(&self, range: Range<usize>) -> usize {
+        assert!(range.start <= range.end && range.end <= self.bv.len());
+
+        self.rank(range.end) - self.rank(range.start)
+    }
+
+    /// Number of set bits before `idx`.
+    pub fn rank(&self, idx: usize) -> usize {
+        let blocks = self.bv.storage();
+        let (block, bit) = (idx / 32, idx % 32);
+
+        let before = if self.ranks.is_empty() {
+            blocks[..block].iter().map(|b| b.count_ones() as usize).sum()
+        } else {
+            self.ranks[block]
+        };
+
+        if bit == 0 {
+            before
+        } else {
+            before + (blocks[block] & ((1u32 << bit) - 1)).count_ones() as usize
+        }
+    }
+
+    /// Index of the set bit `k`, counting from zero, or `None` if there
+    /// are `k` set bits or fewer. Without an index the blocks are counted
+    /// one by one up to the bit.
+    pub fn select(&self, k: usize) -> Option<usize> {
+        if k >= self.total_density {
+            return None;
+        }
+
+        let blocks = self.bv.storage();
+
+        // the block of the bit and the number of set bits before it
+        let (block, before) = if self.ranks.is_empty() {
+            let mut block = 0;
+            let mut before = 0;
+            while before + blocks[block].count_ones() as usize <= k {
+                before += blocks[block].count_ones() as usize;
+                block += 1;
+            }
+
+            (block, before)
+        } else {
+            let block = self.ranks.partition_point(|&rank| rank <= k) - 1;
+
+            (block, self.ranks[block])
+        };
+
+        let mut bits = blocks[block];
+        for _ in before..k {
+            // clear the lowest set bit
+            bits &= bits - 1;
+        }
+
+        Some(block * 32 + bits.trailing_zeros() as usize)
+    }
+
+    pub fn write<W: Write>(
+        &self,
+        mut writer: W
+    ) -> io::Result<()>
+    {
+        let len = u32::try_from(self.bv.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "too many elements in the density map to serialize")
+        })?;
+        writer.write_u32::<BigEndian>(len)?;
+        writer.write_all(&self.bv.to_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(
+        mut reader: R
+    ) -> io::Result<Self>
+    {
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let num_bytes = len.div_ceil(8);
+        // the length is not trusted before the bytes are actually there,
+        // so the buffer only grows with the data that has been read
+        let mut bytes = Vec::with_capacity(std::cmp::min(num_bytes, 1 << 16));
+        reader.take(num_bytes as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != num_bytes {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "expected more bytes of the density map"));
+        }
+
+        let mut bv = BitVec::from_bytes(&bytes);
+        bv.truncate(len);
+        let mut tracker = DensityTracker {
+            bv,
+            total_density: 0,
+            ranks: vec![]
+        };
+        tracker.build_index();
+        tracker.total_density = *tracker.ranks.last().unwrap();
+
+        Ok(tracker)
+    }
+}
+#[cfg(test)]
+fn naive_query_range(density: &DensityTracker, range: Range<usize>) -> usize {
+    density.iter().skip(range.start).take(range.end - range.start).filter(|d| *d).count()
+}
+
+#[test]
+fn test_density_query_range_and_select() {
+    use rand::{XorShiftRng, SeedableRng, Rng};
+
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    for &len in &[0, 1, 31, 32, 33, 64, 100, 1000] {
+        for &fill in &[0.0, 0.5, 1.0] {
+            let mut density = DensityTracker::new();
+            for i in 0..len {
+                density.add_element();
+                if rng.gen::<f64>() < fill {
+                    density.inc(i);
+                }
+            }
+
+            let ranges: Vec<_> = (0..20).map(|_| {
+                let (a, b) = (rng.gen_range(0, len + 1), rng.gen_range(0, len + 1));
+                if a < b { a..b } else { b..a }
+            }).chain(iter::once(0..len)).collect();
+
+            let expected: Vec<_> = ranges.iter().map(|r| naive_query_range(&density, r.clone())).collect();
+            let set_bits: Vec<_> = density.iter().enumerate().filter(|(_, d)| *d).map(|(i, _)| Some(i)).chain(iter::once(None)).collect();
+
+            let unindexed: Vec<_> = ranges.iter().map(|r| density.query_range(r.clone())).collect();
+            let unindexed_select: Vec<_> = (0..set_bits.len()).map(|k| density.select(k)).collect();
+            density.build_index();
+            let indexed: Vec<_> = ranges.iter().map(|r| density.query_range(r.clone())).collect();
+            let indexed_select: Vec<_> = (0..set_bits.len()).map(|k| density.select(k)).collect();
+
+            assert_eq!(unindexed, expected);
+            assert_eq!(indexed, expected);
+            assert_eq!(unindexed_select, set_bits);
+            assert_eq!(indexed_select, set_bits);
+            for (k, i) in set_bits.iter().enumerate() {
+                if let Some(i) = *i {
+                    assert_eq!(density.rank(i), k);
+                }
+            }
+            assert_eq!(density.query_range(0..len), density.get_total_density());
+            if fill == 0.0 {
+                assert_eq!(density.get_total_density(), 0);
+            }
+            if fill == 1.0 {
+                assert_eq!(density.get_total_density(), len);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_density_serialization() {
+    use rand::{XorShiftRng, SeedableRng, Rng};
+
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    for &len in &[0, 7, 8, 45, 1000] {
+        let mut density = DensityTracker::new();
+        for i in 0..len {
+            density.add_element();
+            if rng.gen() {
+                density.inc(i);
+            }
+        }
+
+        let mut bytes = vec![];
+        density.write(&mut bytes).unwrap();
+        let read = DensityTracker::read(&bytes[..]).unwrap();
+
+        assert_eq!(read, density);
+        assert_eq!(read.get_total_density(), density.get_total_density());
+        for start in 0..len.min(50) {
+            assert_eq!(read.query_range(start..len), naive_query_range(&density, start..len));
+        }
+
+        if len > 0 {
+            assert!(DensityTracker::read(&bytes[..(bytes.len() - 1)]).is_err());
+        }
+    }
+
+    // the largest length with no bytes behind it
+    match DensityTracker::read(&u32::MAX.to_be_bytes()[..]) {
+        Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {},
+        _ => panic!("expected the density map to end")
+    }
+}
@@ -0,0 +1,942 @@
+//! This module contains `TestConstraintSystem`, a constraint system that
+//! remembers the names and values of all variables and constraints, so
+//! gadget authors can find out which constraint is not satisfied,
+//! `ConstraintProfiler`, which counts the constraints of every namespace,
+//! `analyze_circuit`, which counts how often every variable is used, and
+//! `DeterministicRng`, a seedable RNG for reproducible test vectors.
+
+use crate::pairing::{
+    Engine
+};
+
+use crate::pairing::ff::{
+    Field
+};
+
+use crate::{
+    Circuit,
+    ConstraintSystem,
+    LinearCombination,
+    SynthesisError,
+    Variable,
+    Index
+};
+
+use rand::{Rng, SeedableRng, ChaChaRng};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::marker::PhantomData;
+
+type NamedConstraint<E> = (LinearCombination<E>, LinearCombination<E>, LinearCombination<E>, String);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NamedObject {
+    Constraint(usize),
+    Var(Variable),
+    Namespace
+}
+
+/// Constraint system for testing purposes. Every variable and constraint
+/// is stored under its full path in the namespaces, e.g. `a/b/c`.
+pub struct TestConstraintSystem<E: Engine> {
+    named_objects: HashMap<String, NamedObject>,
+    current_namespace: Vec<String>,
+    constraints: Vec<NamedConstraint<E>>,
+    inputs: Vec<(E::Fr, String)>,
+    aux: Vec<(E::Fr, String)>
+}
+
+impl<E: Engine> Default for TestConstraintSystem<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Engine> TestConstraintSystem<E> {
+    pub fn new() -> TestConstraintSystem<E> {
+        let mut named_objects = HashMap::new();
+        named_objects.insert("ONE".into(), NamedObject::Var(TestConstraintSystem::<E>::one()));
+
+        TestConstraintSystem {
+            named_objects,
+            current_namespace: vec![],
+            constraints: vec![],
+            inputs: vec![(E::Fr::one(), "ONE".into())],
+            aux: vec![]
+        }
+    }
+
+    /// Returns the path of the first constraint that is not satisfied.
+    pub fn which_is_unsatisfied(&self) -> Option<String> {
+        for (a, b, c, path) in &self.constraints {
+            let mut a = self.eval_lc(a);
+            a.mul_assign(&self.eval_lc(b));
+
+            if a != self.eval_lc(c) {
+                return Some(path.clone());
+            }
+        }
+
+        None
+    }
+
+    pub fn is_satisfied(&self) -> bool {
+        self.which_is_unsatisfied().is_none()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// Number of public inputs, including the "one" input.
+    pub fn num_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Returns the value of the variable at `path`.
+    pub fn get(&self, path: &str) -> E::Fr {
+        match self.named_objects.get(path) {
+            Some(NamedObject::Var(Variable(Index::Input(index)))) => self.inputs[*index].0,
+            Some(NamedObject::Var(Variable(Index::Aux(index)))) => self.aux[*index].0,
+            Some(_) => panic!("tried to get the value of a non-variable at path: {}", path),
+            None => panic!("no variable exists at path: {}", path)
+        }
+    }
+
+    /// Overwrites the value of the variable at `path`, e.g. to check
+    /// that a gadget rejects a wrong witness.
+    pub fn set(&mut self, path: &str, value: E::Fr) {
+        match self.named_objects.get(path) {
+            Some(NamedObject::Var(Variable(Index::Input(index)))) => self.inputs[*index].0 = value,
+            Some(NamedObject::Var(Variable(Index::Aux(index)))) => self.aux[*index].0 = value,
+            Some(_) => panic!("tried to set the value of a non-variable at path: {}", path),
+            None => panic!("no variable exists at path: {}", path)
+        }
+    }
+
+    fn eval_lc(&self, lc: &LinearCombination<E>) -> E::Fr {
+        let mut acc = E::Fr::zero();
+
+        for &(var, ref coeff) in lc.as_ref() {
+            let mut tmp = match var.get_unchecked() {
+                Index::Input(index) => self.inputs[index].0,
+                Index::Aux(index) => self.aux[index].0
+            };
+
+            tmp.mul_assign(coeff);
+            acc.add_assign(&tmp);
+        }
+
+        acc
+    }
+
+    fn compute_path(&self, name: &str) -> String {
+        assert!(!name.contains('/'), "'/' is not allowed in names: {}", name);
+
+        let mut path = self.current_namespace.join("/");
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(name);
+
+        path
+    }
+
+    fn set_named_obj(&mut self, path: String, to: NamedObject) {
+        if self.named_objects.contains_key(&path) {
+            panic!("tried to create object at existing path: {}", path);
+        }
+
+        self.named_objects.insert(path, to);
+    }
+}
+
+impl<E: Engine> ConstraintSystem<E> for TestConstraintSystem<E> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(
+        &mut self,
+        annotation: A,
+        f: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let index = self.aux.len();
+        let path = self.compute_path(&annotation().into());
+        self.aux.push((f()?, path.clone()));
+        let var = Variable(Index::Aux(index));
+        self.set_named_obj(path, NamedObject::Var(var));
+
+        Ok(var)
+    }
+
+    fn alloc_input<F, A, AR>(
+        &mut self,
+        annotation: A,
+        f: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let index = self.inputs.len();
+        let path = self.compute_path(&annotation().into());
+        self.inputs.push((f()?, path.clone()));
+        let var = Variable(Index::Input(index));
+        self.set_named_obj(path, NamedObject::Var(var));
+
+        Ok(var)
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(
+        &mut self,
+        annotation: A,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where A: FnOnce() -> AR, AR: Into<String>,
+              LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        let path = self.compute_path(&annotation().into());
+        let index = self.constraints.len();
+        self.set_named_obj(path.clone(), NamedObject::Constraint(index));
+
+        let a = a(LinearCombination::zero());
+        let b = b(LinearCombination::zero());
+        let c = c(LinearCombination::zero());
+
+        self.constraints.push((a, b, c, path));
+    }
+
+    fn enforce_unnamed<LA, LB, LC>(
+        &mut self,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        let index = self.constraints.len();
+        self.enforce(|| format!("unnamed constraint {}", index), a, b, c)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+        where NR: Into<String>, N: FnOnce() -> NR
+    {
+        let name = name_fn().into();
+        let path = self.compute_path(&name);
+        self.set_named_obj(path, NamedObject::Namespace);
+        self.current_namespace.push(name);
+    }
+
+    fn pop_namespace(&mut self)
+    {
+        assert!(self.current_namespace.pop().is_some());
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root
+    {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct ProfiledNamespace {
+    name: String,
+    parent: usize,
+    children: HashMap<String, usize>,
+    num_inputs: usize,
+    num_aux: usize,
+    num_constraints: usize
+}
+
+/// Constraint system that only counts the variables and constraints of
+/// every namespace. Values, annotations and linear combinations are never
+/// evaluated, and each namespace name is stored once no matter how often
+/// it is entered, so it can run over circuits with millions of constraints.
+pub struct ConstraintProfiler<E: Engine> {
+    // namespaces are only appended, so a parent always precedes its children
+    namespaces: Vec<ProfiledNamespace>,
+    current: usize,
+    num_inputs: usize,
+    num_aux: usize,
+    _marker: PhantomData<E>
+}
+
+impl<E: Engine> Default for ConstraintProfiler<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Engine> ConstraintProfiler<E> {
+    pub fn new() -> ConstraintProfiler<E> {
+        let root = ProfiledNamespace {
+            name: String::new(),
+            parent: 0,
+            children: HashMap::new(),
+            num_inputs: 1,
+            num_aux: 0,
+            num_constraints: 0
+        };
+
+        ConstraintProfiler {
+            namespaces: vec![root],
+            current: 0,
+            num_inputs: 1,
+            num_aux: 0,
+            _marker: PhantomData
+        }
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.namespaces.iter().map(|n| n.num_constraints).sum()
+    }
+
+    fn path(&self, mut index: usize) -> String {
+        let mut segments = vec![];
+        while index != 0 {
+            segments.push(&self.namespaces[index].name[..]);
+            index = self.namespaces[index].parent;
+        }
+        segments.reverse();
+
+        segments.join("/")
+    }
+
+    /// Number of constraints and variables of every namespace, including
+    /// its subnamespaces.
+    fn rollups(&self) -> Vec<(usize, usize)> {
+        let mut rollups = self.namespaces.iter()
+            .map(|n| (n.num_constraints, n.num_inputs + n.num_aux))
+            .collect::<Vec<_>>();
+
+        for index in (1..self.namespaces.len()).rev() {
+            let parent = self.namespaces[index].parent;
+            rollups[parent].0 += rollups[index].0;
+            rollups[parent].1 += rollups[index].1;
+        }
+
+        rollups
+    }
+
+    /// Returns `(path, constraints, variables)` for every namespace, where
+    /// the counts include all subnamespaces. The root has the empty path.
+    /// Sorted by the number of constraints, largest first.
+    pub fn namespaces(&self) -> Vec<(String, usize, usize)> {
+        let rollups = self.rollups();
+
+        let mut result = rollups.into_iter().enumerate()
+            .map(|(index, (constraints, variables))| (self.path(index), constraints, variables))
+            .collect::<Vec<_>>();
+        result.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        result
+    }
+
+    /// Human-readable tree of the namespaces, with the children of every
+    /// namespace sorted by the number of constraints.
+    pub fn report(&self) -> String {
+        let rollups = self.rollups();
+
+        let mut report = String::new();
+        let mut stack = vec![(0, 0)];
+        while let Some((index, depth)) = stack.pop() {
+            let namespace = &self.namespaces[index];
+            let name = if index == 0 { "<root>" } else { &namespace.name[..] };
+            report.push_str(&format!(
+                "{}{}: {} constraints, {} variables\n",
+                "  ".repeat(depth),
+                name,
+                rollups[index].0,
+                rollups[index].1
+            ));
+
+            let mut children = namespace.children.values().cloned().collect::<Vec<_>>();
+            // popped from the end, so the largest child has to be the last one
+            children.sort_by(|a, b| rollups[*a].0.cmp(&rollups[*b].0).then_with(|| self.namespaces[*b].name.cmp(&self.namespaces[*a].name)));
+            stack.extend(children.into_iter().map(|child| (child, depth + 1)));
+        }
+
+        report
+    }
+}
+
+impl<E: Engine> ConstraintSystem<E> for ConstraintProfiler<E> {
+    type Root = Self;
+
+    const WANTS_ANNOTATIONS: bool = false;
+
+    fn alloc<F, A, AR>(
+        &mut self,
+        _: A,
+        _: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        self.namespaces[self.current].num_aux += 1;
+        self.num_aux += 1;
+
+        Ok(Variable(Index::Aux(self.num_aux - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(
+        &mut self,
+        _: A,
+        _: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        self.namespaces[self.current].num_inputs += 1;
+        self.num_inputs += 1;
+
+        Ok(Variable(Index::Input(self.num_inputs - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(
+        &mut self,
+        _: A,
+        _: LA,
+        _: LB,
+        _: LC
+    )
+        where A: FnOnce() -> AR, AR: Into<String>,
+              LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        self.namespaces[self.current].num_constraints += 1;
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+        where NR: Into<String>, N: FnOnce() -> NR
+    {
+        let name = name_fn().into();
+
+        let child = match self.namespaces[self.current].children.get(&name) {
+            Some(&child) => child,
+            None => {
+                let child = self.namespaces.len();
+                self.namespaces[self.current].children.insert(name.clone(), child);
+                self.namespaces.push(ProfiledNamespace {
+                    name,
+                    parent: self.current,
+                    children: HashMap::new(),
+                    num_inputs: 0,
+                    num_aux: 0,
+                    num_constraints: 0
+                });
+
+                child
+            }
+        };
+
+        self.current = child;
+    }
+
+    fn pop_namespace(&mut self)
+    {
+        assert!(self.current != 0, "popped the root namespace");
+        self.current = self.namespaces[self.current].parent;
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root
+    {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct InternedNamespace {
+    name: String,
+    parent: usize,
+    children: HashMap<String, usize>
+}
+
+#[derive(Clone, Copy, Debug)]
+struct VariableUsage {
+    namespace: u32,
+    name: u32,
+    // number of terms of the variable in A, B and C
+    occurrences: [u32; 3]
+}
+
+/// One of the most used variables of a circuit, see `CircuitAnalysis::hottest`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HotVariable {
+    pub variable: Variable,
+    pub path: String,
+    /// Number of terms of the variable in A, B and C
+    pub occurrences: [u32; 3]
+}
+
+impl HotVariable {
+    pub fn total(&self) -> u64 {
+        self.occurrences.iter().map(|&o| o as u64).sum()
+    }
+}
+
+/// How often every variable is used by the constraints of a circuit and
+/// how large its linear combinations are, see `analyze_circuit`. Counters
+/// are `u32` and the names of namespaces and variables are stored once,
+/// so circuits with millions of constraints can be analyzed.
+pub struct CircuitAnalysis {
+    namespaces: Vec<InternedNamespace>,
+    names: Vec<String>,
+    inputs: Vec<VariableUsage>,
+    aux: Vec<VariableUsage>,
+    num_constraints: usize,
+    term_histograms: [BTreeMap<usize, usize>; 3]
+}
+
+impl CircuitAnalysis {
+    pub fn num_constraints(&self) -> usize {
+        self.num_constraints
+    }
+
+    /// Number of public inputs, including the "one" input.
+    pub fn num_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn num_aux(&self) -> usize {
+        self.aux.len()
+    }
+
+    /// Number of linear combinations in A, B and C by their number of terms
+    pub fn term_histograms(&self) -> &[BTreeMap<usize, usize>; 3] {
+        &self.term_histograms
+    }
+
+    /// The `k` variables with the most terms in all the constraints, most
+    /// used first. The "one" input is included under the path `ONE`.
+    pub fn hottest(&self, k: usize) -> Vec<HotVariable> {
+        let mut variables = self.inputs.iter().enumerate().map(|(i, usage)| (Variable(Index::Input(i)), usage))
+            .chain(self.aux.iter().enumerate().map(|(i, usage)| (Variable(Index::Aux(i)), usage)))
+            .collect::<Vec<_>>();
+
+        let total = |usage: &VariableUsage| usage.occurrences.iter().map(|&o| o as u64).sum::<u64>();
+        variables.sort_unstable_by(|a, b| total(b.1).cmp(&total(a.1)).then_with(|| a.0.cmp(&b.0)));
+
+        variables.into_iter().take(k).map(|(variable, usage)| HotVariable {
+            variable,
+            path: self.path(usage),
+            occurrences: usage.occurrences
+        }).collect()
+    }
+
+    fn path(&self, usage: &VariableUsage) -> String {
+        let mut segments = vec![&self.names[usage.name as usize][..]];
+        let mut index = usage.namespace as usize;
+        while index != 0 {
+            segments.push(&self.namespaces[index].name[..]);
+            index = self.namespaces[index].parent;
+        }
+        segments.reverse();
+
+        segments.join("/")
+    }
+}
+
+impl fmt::Display for CircuitAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        writeln!(f, "{} constraints, {} inputs, {} auxiliary variables", self.num_constraints, self.inputs.len(), self.aux.len())?;
+
+        writeln!(f, "Terms per linear combination:")?;
+        for (name, histogram) in ["A", "B", "C"].iter().zip(self.term_histograms.iter()) {
+            let counts = histogram.iter().map(|(terms, count)| format!("{} x {}", count, terms)).collect::<Vec<_>>();
+            writeln!(f, "  {}: {}", name, counts.join(", "))?;
+        }
+
+        writeln!(f, "Hottest variables:")?;
+        for hot in self.hottest(10) {
+            writeln!(
+                f,
+                "  {} ({}): {} terms, {} in A, {} in B, {} in C",
+                hot.path,
+                hot.variable,
+                hot.total(),
+                hot.occurrences[0],
+                hot.occurrences[1],
+                hot.occurrences[2]
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Synthesizes the `circuit` without a witness and counts the terms of
+/// every variable, see `CircuitAnalysis`.
+pub fn analyze_circuit<E: Engine, C: Circuit<E>>(
+    circuit: C
+) -> Result<CircuitAnalysis, SynthesisError>
+{
+    let mut analyzer = CircuitAnalyzer::<E> {
+        analysis: CircuitAnalysis {
+            namespaces: vec![InternedNamespace {
+                name: String::new(),
+                parent: 0,
+                children: HashMap::new()
+            }],
+            names: vec![],
+            inputs: vec![],
+            aux: vec![],
+            num_constraints: 0,
+            term_histograms: [BTreeMap::new(), BTreeMap::new(), BTreeMap::new()]
+        },
+        name_ids: HashMap::new(),
+        current: 0,
+        _marker: PhantomData
+    };
+
+    let usage = analyzer.usage("ONE".into());
+    analyzer.analysis.inputs.push(usage);
+
+    circuit.synthesize(&mut analyzer)?;
+
+    Ok(analyzer.analysis)
+}
+
+struct CircuitAnalyzer<E: Engine> {
+    analysis: CircuitAnalysis,
+    name_ids: HashMap<String, u32>,
+    current: usize,
+    _marker: PhantomData<E>
+}
+
+impl<E: Engine> CircuitAnalyzer<E> {
+    fn usage(&mut self, name: String) -> VariableUsage {
+        let names = &mut self.analysis.names;
+        let name = *self.name_ids.entry(name).or_insert_with_key(|name| {
+            names.push(name.clone());
+            (names.len() - 1) as u32
+        });
+
+        VariableUsage {
+            namespace: self.current as u32,
+            name,
+            occurrences: [0; 3]
+        }
+    }
+}
+
+impl<E: Engine> ConstraintSystem<E> for CircuitAnalyzer<E> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(
+        &mut self,
+        annotation: A,
+        _: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let usage = self.usage(annotation().into());
+        self.analysis.aux.push(usage);
+
+        Ok(Variable(Index::Aux(self.analysis.aux.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(
+        &mut self,
+        annotation: A,
+        _: F
+    ) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        let usage = self.usage(annotation().into());
+        self.analysis.inputs.push(usage);
+
+        Ok(Variable(Index::Input(self.analysis.inputs.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(
+        &mut self,
+        _: A,
+        a: LA,
+        b: LB,
+        c: LC
+    )
+        where A: FnOnce() -> AR, AR: Into<String>,
+              LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        let lcs = [a(LinearCombination::zero()), b(LinearCombination::zero()), c(LinearCombination::zero())];
+
+        for (i, lc) in lcs.iter().enumerate() {
+            *self.analysis.term_histograms[i].entry(lc.len()).or_insert(0) += 1;
+
+            for (var, _) in lc.iter() {
+                let usage = match var.get_unchecked() {
+                    Index::Input(index) => &mut self.analysis.inputs[index],
+                    Index::Aux(index) => &mut self.analysis.aux[index]
+                };
+                usage.occurrences[i] = usage.occurrences[i].saturating_add(1);
+            }
+        }
+
+        self.analysis.num_constraints += 1;
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+        where NR: Into<String>, N: FnOnce() -> NR
+    {
+        let name = name_fn().into();
+        let namespaces = &mut self.analysis.namespaces;
+
+        let child = match namespaces[self.current].children.get(&name) {
+            Some(&child) => child,
+            None => {
+                let child = namespaces.len();
+                namespaces[self.current].children.insert(name.clone(), child);
+                namespaces.push(InternedNamespace {
+                    name,
+                    parent: self.current,
+                    children: HashMap::new()
+                });
+
+                child
+            }
+        };
+
+        self.current = child;
+    }
+
+    fn pop_namespace(&mut self)
+    {
+        assert!(self.current != 0, "popped the root namespace");
+        self.current = self.analysis.namespaces[self.current].parent;
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root
+    {
+        self
+    }
+}
+
+/// Seedable RNG for reproducible test vectors, e.g. proofs that can be
+/// compared byte for byte. The stream is defined by ChaCha20, so it does
+/// not depend on the platform or on the version of the `XorShiftRng`
+/// implementation.
+pub struct DeterministicRng(ChaChaRng);
+
+impl DeterministicRng {
+    /// Two RNGs from the same seed produce the same stream.
+    pub fn from_seed(seed: u64) -> Self {
+        DeterministicRng(ChaChaRng::from_seed(&[seed as u32, (seed >> 32) as u32]))
+    }
+}
+
+impl Rng for DeterministicRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+}
+
+#[test]
+fn test_unsatisfied_constraint_is_localized() {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::PrimeField;
+    use crate::Circuit;
+    use crate::tests::xor_demo;
+
+    let mut cs = TestConstraintSystem::<Bls12>::new();
+
+    {
+        let mut cs = cs.namespace(|| "xor");
+        xor_demo::<Bls12>(Some(true), Some(false)).synthesize(&mut cs).unwrap();
+    }
+
+    assert!(cs.is_satisfied());
+    assert_eq!(cs.which_is_unsatisfied(), None);
+    assert_eq!(cs.num_constraints(), 3);
+    assert_eq!(cs.num_inputs(), 2);
+    assert_eq!(cs.get("ONE"), Fr::one());
+    assert_eq!(cs.get("xor/a"), Fr::one());
+    assert_eq!(cs.get("xor/b"), Fr::zero());
+    assert_eq!(cs.get("xor/c"), Fr::one());
+
+    // the booleanity of b is enforced by its own constraint
+    cs.set("xor/b", Fr::from_str("2").unwrap());
+    assert_eq!(cs.which_is_unsatisfied(), Some("xor/b_boolean_constraint".to_owned()));
+
+    // a boolean, but wrong b only breaks the xor constraint
+    cs.set("xor/b", Fr::one());
+    assert_eq!(cs.which_is_unsatisfied(), Some("xor/c_xor_constraint".to_owned()));
+
+    cs.set("xor/c", Fr::zero());
+    assert!(cs.is_satisfied());
+}
+
+#[test]
+#[should_panic(expected = "tried to create object at existing path: a")]
+fn test_duplicate_path_panics() {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+
+    let mut cs = TestConstraintSystem::<Bls12>::new();
+    cs.alloc(|| "a", || Ok(Fr::one())).unwrap();
+    cs.alloc(|| "a", || Ok(Fr::one())).unwrap();
+}
+
+#[test]
+fn test_constraint_profiler_counts_namespaces() {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::Circuit;
+
+    struct Nested;
+
+    impl Circuit<Bls12> for Nested {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS
+        ) -> Result<(), SynthesisError>
+        {
+            let x = cs.alloc_input(|| "x", || Err(SynthesisError::AssignmentMissing))?;
+            cs.enforce(|| "x", |lc| lc + x, |lc| lc + CS::one(), |lc| lc + x);
+
+            for i in 0..3 {
+                let mut cs = cs.namespace(|| "hash");
+                let mut cs = cs.namespace(|| format!("round {}", i));
+                for _ in 0..(i + 1) {
+                    let y = cs.alloc(|| "y", || Ok(Fr::one()))?;
+                    cs.enforce(|| "y", |lc| lc + y, |lc| lc + y, |lc| lc + y);
+                    cs.enforce(|| "z", |lc| lc + y, |lc| lc + x, |lc| lc + y);
+                }
+            }
+
+            let mut cs = cs.namespace(|| "range");
+            for _ in 0..5 {
+                cs.enforce(|| "bit", |lc| lc + x, |lc| lc + x, |lc| lc + x);
+            }
+
+            Ok(())
+        }
+    }
+
+    let mut cs = ConstraintProfiler::<Bls12>::new();
+    Nested.synthesize(&mut cs).unwrap();
+
+    assert_eq!(cs.num_constraints(), 18);
+    assert_eq!(cs.namespaces(), vec![
+        ("".to_owned(), 18, 8),
+        ("hash".to_owned(), 12, 6),
+        ("hash/round 2".to_owned(), 6, 3),
+        ("range".to_owned(), 5, 0),
+        ("hash/round 1".to_owned(), 4, 2),
+        ("hash/round 0".to_owned(), 2, 1)
+    ]);
+
+    assert_eq!(cs.report(), "\
+<root>: 18 constraints, 8 variables
+  hash: 12 constraints, 6 variables
+    round 2: 6 constraints, 3 variables
+    round 1: 4 constraints, 2 variables
+    round 0: 2 constraints, 1 variables
+  range: 5 constraints, 0 variables
+");
+}
+
+#[test]
+fn test_mul_allocate() {
+    use crate::pairing::bls12_381::{Bls12, Fr};
+    use crate::pairing::ff::PrimeField;
+
+    let fr = |v: &str| Fr::from_str(v).unwrap();
+
+    let mut cs = TestConstraintSystem::<Bls12>::new();
+    let x = cs.alloc(|| "x", || Ok(fr("3"))).unwrap();
+    let y = cs.alloc(|| "y", || Ok(fr("4"))).unwrap();
+
+    let z = {
+        let mut cs = cs.namespace(|| "product");
+        let a = LinearCombination::zero() + x + TestConstraintSystem::<Bls12>::one();
+        let b = LinearCombination::zero() + (fr("2"), y);
+        cs.mul_allocate(|| "z", &a, &b, || Ok(fr("32"))).unwrap()
+    };
+
+    assert!(cs.is_satisfied());
+    assert_eq!(cs.num_constraints(), 1);
+    assert_eq!(cs.get("product/z"), fr("32"));
+    assert_eq!(z, Variable(Index::Aux(2)));
+
+    cs.set("product/z", fr("31"));
+    assert_eq!(cs.which_is_unsatisfied(), Some("product/z_constraint".to_owned()));
+}
+
+#[test]
+fn test_analyze_circuit_finds_the_hot_variable() {
+    use crate::pairing::bls12_381::Bls12;
+
+    struct Hot;
+
+    impl Circuit<Bls12> for Hot {
+        fn synthesize<CS: ConstraintSystem<Bls12>>(
+            self,
+            cs: &mut CS
+        ) -> Result<(), SynthesisError>
+        {
+            let hot = {
+                let mut cs = cs.namespace(|| "outer");
+                let mut cs = cs.namespace(|| "inner");
+                cs.alloc(|| "hot", || Err(SynthesisError::AssignmentMissing))?
+            };
+
+            for i in 0..50 {
+                let mut cs = cs.namespace(|| format!("step {}", i));
+                let x = cs.alloc(|| "x", || Err(SynthesisError::AssignmentMissing))?;
+                cs.enforce(|| "x", |lc| lc + hot + x, |lc| lc + hot, |lc| lc + x + CS::one());
+            }
+
+            Ok(())
+        }
+    }
+
+    let analysis = analyze_circuit::<Bls12, _>(Hot).unwrap();
+
+    assert_eq!(analysis.num_constraints(), 50);
+    assert_eq!(analysis.num_inputs(), 1);
+    assert_eq!(analysis.num_aux(), 51);
+    assert_eq!(analysis.term_histograms()[0], vec![(2, 50)].into_iter().collect());
+    assert_eq!(analysis.term_histograms()[1], vec![(1, 50)].into_iter().collect());
+    assert_eq!(analysis.term_histograms()[2], vec![(2, 50)].into_iter().collect());
+
+    let hottest = analysis.hottest(3);
+    assert_eq!(hottest[0], HotVariable {
+        variable: Variable(Index::Aux(0)),
+        path: "outer/inner/hot".into(),
+        occurrences: [50, 50, 0]
+    });
+    assert_eq!(hottest[1].path, "ONE");
+    assert_eq!(hottest[1].occurrences, [0, 0, 50]);
+    assert_eq!(hottest[2].path, "step 0/x");
+    assert_eq!(hottest[2].total(), 2);
+
+    let report = analysis.to_string();
+    assert!(report.starts_with("50 constraints, 1 inputs, 51 auxiliary variables\n"));
+    assert!(report.contains("  A: 50 x 2\n"));
+    assert!(report.contains("  outer/inner/hot (aux(0)): 100 terms, 50 in A, 50 in B, 0 in C\n"));
+}
+
+#[test]
+fn test_deterministic_rng_is_reproducible() {
+    let stream = |seed| {
+        let mut rng = DeterministicRng::from_seed(seed);
+        (0..16).map(|_| rng.next_u64()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(stream(42), stream(42));
+    assert!(stream(42) != stream(43));
+}
@@ -8,6 +8,7 @@ use crate::pairing::ff:: {
 };
 
 pub mod dummy_engine;
+pub(crate) mod test_circuits;
 use self::dummy_engine::*;
 
 use std::marker::PhantomData;
@@ -24,6 +25,14 @@ pub(crate) struct XORDemo<E: Engine> {
     pub(crate) _marker: PhantomData<E>
 }
 
+pub(crate) fn xor_demo<E: Engine>(a: Option<bool>, b: Option<bool>) -> XORDemo<E> {
+    XORDemo {
+        a,
+        b,
+        _marker: PhantomData
+    }
+}
+
 impl<E: Engine> Circuit<E> for XORDemo<E> {
     fn synthesize<CS: ConstraintSystem<E>>(
         self,
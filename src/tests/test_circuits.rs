@@ -0,0 +1,375 @@
+//! Synthetic circuits with controllable constraint shapes that are used
+//! as a shared corpus for benchmarks and regression tests.
+
+use crate::pairing::{
+    Engine
+};
+
+use crate::pairing::ff::{
+    Field,
+    PrimeField
+};
+
+use rand::{XorShiftRng, SeedableRng, Rand, Rng};
+
+use std::marker::PhantomData;
+
+use crate::{
+    Circuit,
+    ConstraintSystem,
+    SynthesisError,
+    LinearCombination,
+    Variable,
+    Index
+};
+
+const SEED: [u32; 4] = [0x5dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654];
+
+/// Every constraint is `(sum of terms_per_lc random multiples of witnesses) * 1 = out`.
+pub(crate) struct RandomLinearCircuit<E: Engine> {
+    pub(crate) num_constraints: usize,
+    pub(crate) terms_per_lc: usize,
+    _marker: PhantomData<E>
+}
+
+pub(crate) fn random_linear_circuit<E: Engine>(num_constraints: usize, terms_per_lc: usize) -> RandomLinearCircuit<E> {
+    RandomLinearCircuit {
+        num_constraints,
+        terms_per_lc,
+        _marker: PhantomData
+    }
+}
+
+impl<E: Engine> Circuit<E> for RandomLinearCircuit<E> {
+    fn synthesize<CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS
+    ) -> Result<(), SynthesisError>
+    {
+        let rng = &mut XorShiftRng::from_seed(SEED);
+
+        let mut witnesses = vec![];
+        for i in 0..self.terms_per_lc {
+            let value = E::Fr::rand(rng);
+            let var = cs.alloc(|| format!("witness {}", i), || Ok(value))?;
+            witnesses.push((var, value));
+        }
+
+        for i in 0..self.num_constraints {
+            let mut lc = LinearCombination::<E>::zero();
+            let mut value = E::Fr::zero();
+
+            for _ in 0..self.terms_per_lc {
+                let (var, mut tmp) = witnesses[rng.gen_range(0, witnesses.len())];
+                let coeff = E::Fr::rand(rng);
+                lc = lc + (coeff, var);
+                tmp.mul_assign(&coeff);
+                value.add_assign(&tmp);
+            }
+
+            let out = cs.alloc(|| format!("out {}", i), || Ok(value))?;
+
+            cs.enforce(
+                || format!("linear combination {}", i),
+                |_| lc,
+                |lc| lc + CS::one(),
+                |lc| lc + out
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Allocates `num_bits` booleans and XORs all of them together into the
+/// single public input.
+pub(crate) struct BooleanChain<E: Engine> {
+    pub(crate) num_bits: usize,
+    _marker: PhantomData<E>
+}
+
+pub(crate) fn boolean_chain<E: Engine>(num_bits: usize) -> BooleanChain<E> {
+    assert!(num_bits > 0);
+
+    BooleanChain {
+        num_bits,
+        _marker: PhantomData
+    }
+}
+
+impl<E: Engine> Circuit<E> for BooleanChain<E> {
+    fn synthesize<CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS
+    ) -> Result<(), SynthesisError>
+    {
+        let rng = &mut XorShiftRng::from_seed(SEED);
+
+        let to_fr = |b: bool| if b { E::Fr::one() } else { E::Fr::zero() };
+
+        let mut bits = vec![];
+        for i in 0..self.num_bits {
+            let bit: bool = rng.gen();
+            let var = cs.alloc(|| format!("bit {}", i), || Ok(to_fr(bit)))?;
+
+            cs.enforce(
+                || format!("bit {} is boolean", i),
+                |lc| lc + CS::one() - var,
+                |lc| lc + var,
+                |lc| lc
+            );
+
+            bits.push((var, bit));
+        }
+
+        let (mut acc, mut acc_value) = bits[0];
+
+        for (i, &(var, bit)) in bits.iter().enumerate().skip(1) {
+            let value = acc_value ^ bit;
+            let out = if i == self.num_bits - 1 {
+                cs.alloc_input(|| "result", || Ok(to_fr(value)))?
+            } else {
+                cs.alloc(|| format!("xor {}", i), || Ok(to_fr(value)))?
+            };
+
+            // 2a * b = a + b - (a xor b)
+            cs.enforce(
+                || format!("xor {}", i),
+                |lc| lc + acc + acc,
+                |lc| lc + var,
+                |lc| lc + acc + var - out
+            );
+
+            acc = out;
+            acc_value = value;
+        }
+
+        if self.num_bits == 1 {
+            let out = cs.alloc_input(|| "result", || Ok(to_fr(acc_value)))?;
+
+            cs.enforce(
+                || "result",
+                |lc| lc + acc,
+                |lc| lc + CS::one(),
+                |lc| lc + out
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes `x^(depth + 1)` as a chain of `depth` multiplications, where `x`
+/// is private and the result is public.
+pub(crate) struct MultiplicationChain<E: Engine> {
+    pub(crate) depth: usize,
+    _marker: PhantomData<E>
+}
+
+pub(crate) fn multiplication_chain<E: Engine>(depth: usize) -> MultiplicationChain<E> {
+    assert!(depth > 0);
+
+    MultiplicationChain {
+        depth,
+        _marker: PhantomData
+    }
+}
+
+impl<E: Engine> Circuit<E> for MultiplicationChain<E> {
+    fn synthesize<CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS
+    ) -> Result<(), SynthesisError>
+    {
+        let rng = &mut XorShiftRng::from_seed(SEED);
+
+        let x_value = E::Fr::rand(rng);
+        let x = cs.alloc(|| "x", || Ok(x_value))?;
+
+        let mut acc = x;
+        let mut acc_value = x_value;
+
+        for i in 0..self.depth {
+            acc_value.mul_assign(&x_value);
+
+            let out = if i == self.depth - 1 {
+                cs.alloc_input(|| "result", || Ok(acc_value))?
+            } else {
+                cs.alloc(|| format!("power {}", i + 2), || Ok(acc_value))?
+            };
+
+            cs.enforce(
+                || format!("multiplication {}", i),
+                |lc| lc + acc,
+                |lc| lc + x,
+                |lc| lc + out
+            );
+
+            acc = out;
+        }
+
+        Ok(())
+    }
+}
+
+/// Counts the shape of the constraint system and checks that it is satisfied.
+struct ShapeCounter<E: Engine> {
+    inputs: Vec<E::Fr>,
+    aux: Vec<E::Fr>,
+    num_constraints: usize,
+    num_terms: usize,
+    satisfied: bool
+}
+
+impl<E: Engine> ShapeCounter<E> {
+    fn new() -> Self {
+        ShapeCounter {
+            inputs: vec![E::Fr::one()],
+            aux: vec![],
+            num_constraints: 0,
+            num_terms: 0,
+            satisfied: true
+        }
+    }
+
+    fn eval(&self, lc: &LinearCombination<E>) -> E::Fr {
+        let mut acc = E::Fr::zero();
+
+        for &(var, coeff) in lc.as_ref() {
+            let mut tmp = match var {
+                Variable(Index::Input(i)) => self.inputs[i],
+                Variable(Index::Aux(i)) => self.aux[i]
+            };
+            tmp.mul_assign(&coeff);
+            acc.add_assign(&tmp);
+        }
+
+        acc
+    }
+}
+
+impl<E: Engine> ConstraintSystem<E> for ShapeCounter<E> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        self.aux.push(f()?);
+
+        Ok(Variable(Index::Aux(self.aux.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+        where F: FnOnce() -> Result<E::Fr, SynthesisError>, A: FnOnce() -> AR, AR: Into<String>
+    {
+        self.inputs.push(f()?);
+
+        Ok(Variable(Index::Input(self.inputs.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+        where A: FnOnce() -> AR, AR: Into<String>,
+              LA: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LB: FnOnce(LinearCombination<E>) -> LinearCombination<E>,
+              LC: FnOnce(LinearCombination<E>) -> LinearCombination<E>
+    {
+        let a = a(LinearCombination::zero());
+        let b = b(LinearCombination::zero());
+        let c = c(LinearCombination::zero());
+
+        let mut lhs = self.eval(&a);
+        lhs.mul_assign(&self.eval(&b));
+        if lhs != self.eval(&c) {
+            self.satisfied = false;
+        }
+
+        self.num_constraints += 1;
+        self.num_terms += a.as_ref().len() + b.as_ref().len() + c.as_ref().len();
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+        where NR: Into<String>, N: FnOnce() -> NR
+    {
+    }
+
+    fn pop_namespace(&mut self)
+    {
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+fn shape_of<E: Engine, C: Circuit<E>>(circuit: C) -> ShapeCounter<E> {
+    let mut cs = ShapeCounter::new();
+    circuit.synthesize(&mut cs).unwrap();
+    assert!(cs.satisfied);
+
+    cs
+}
+
+#[test]
+fn test_random_linear_circuit_shape() {
+    use crate::pairing::bn256::Bn256;
+
+    for &(n, terms) in &[(1, 1), (10, 3), (100, 16)] {
+        let cs = shape_of(random_linear_circuit::<Bn256>(n, terms));
+
+        assert_eq!(cs.num_constraints, n);
+        assert_eq!(cs.inputs.len(), 1);
+        assert_eq!(cs.aux.len(), terms + n);
+        assert_eq!(cs.num_terms, n * (terms + 2));
+    }
+}
+
+#[test]
+fn test_boolean_chain_shape() {
+    use crate::pairing::bn256::Bn256;
+
+    for &n in &[1, 2, 64] {
+        let cs = shape_of(boolean_chain::<Bn256>(n));
+
+        assert_eq!(cs.num_constraints, 2 * n - 1 + if n == 1 { 1 } else { 0 });
+        assert_eq!(cs.inputs.len(), 2);
+        assert_eq!(cs.aux.len(), if n == 1 { 1 } else { 2 * n - 2 });
+    }
+}
+
+#[test]
+fn test_multiplication_chain_shape() {
+    use crate::pairing::bn256::Bn256;
+
+    for &depth in &[1, 5, 100] {
+        let cs = shape_of(multiplication_chain::<Bn256>(depth));
+
+        assert_eq!(cs.num_constraints, depth);
+        assert_eq!(cs.inputs.len(), 2);
+        assert_eq!(cs.aux.len(), depth);
+        assert_eq!(cs.num_terms, 3 * depth);
+
+        assert_eq!(cs.inputs[1], cs.aux[0].pow([depth as u64 + 1]));
+    }
+}
+
+#[test]
+fn test_circuits_prove_with_groth16() {
+    use crate::pairing::bls12_381::Bls12;
+    use crate::groth16::{generate_random_parameters, create_random_proof, prepare_verifying_key, verify_proof};
+
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let params = generate_random_parameters::<Bls12, _, _>(random_linear_circuit(16, 4), rng).unwrap();
+    let proof = create_random_proof(random_linear_circuit(16, 4), &params, rng).unwrap();
+    assert!(verify_proof(&prepare_verifying_key(&params.vk), &proof, &[]).unwrap());
+
+    let params = generate_random_parameters::<Bls12, _, _>(boolean_chain(16), rng).unwrap();
+    let public = shape_of(boolean_chain::<Bls12>(16)).inputs[1];
+    let proof = create_random_proof(boolean_chain(16), &params, rng).unwrap();
+    assert!(verify_proof(&prepare_verifying_key(&params.vk), &proof, &[public]).unwrap());
+
+    let params = generate_random_parameters::<Bls12, _, _>(multiplication_chain(16), rng).unwrap();
+    let public = shape_of(multiplication_chain::<Bls12>(16)).inputs[1];
+    let proof = create_random_proof(multiplication_chain(16), &params, rng).unwrap();
+    assert!(verify_proof(&prepare_verifying_key(&params.vk), &proof, &[public]).unwrap());
+}
@@ -0,0 +1,24 @@
+use std::error::Error;
+use std::fmt;
+
+/// A panic that has happened inside of a chunk dispatched
+/// on the worker pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkerError {
+    /// Index of the failed chunk, in the order the chunks were spawned
+    pub chunk: usize,
+    /// Panic message, if it was a string
+    pub message: String
+}
+
+impl Error for WorkerError {
+    fn description(&self) -> &str {
+        "worker chunk has panicked"
+    }
+}
+
+impl fmt::Display for WorkerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "worker chunk {} has panicked: {}", self.chunk, self.message)
+    }
+}
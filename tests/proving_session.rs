@@ -0,0 +1,115 @@
+// Compares the memory allocated by `create_proof` and by a `ProvingSession`
+// for the same proof. The session only skips building the linear combinations
+// of the constraints, so that is all the difference. The allocator of this
+// test binary counts the bytes, so it must only contain this test.
+
+use rand::{XorShiftRng, SeedableRng, Rand};
+
+use bellman_ce::pairing::bls12_381::{Bls12, Fr};
+use bellman_ce::pairing::ff::Field;
+
+use bellman_ce::{
+    Circuit,
+    ConstraintSystem,
+    SynthesisError
+};
+
+use bellman_ce::groth16::{
+    create_proof,
+    generate_random_parameters,
+    ProvingSession
+};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// x^(2^depth), every squaring in its own namespace
+struct Squarings {
+    x: Option<Fr>,
+    depth: usize
+}
+
+impl Circuit<Bls12> for Squarings {
+    fn synthesize<CS: ConstraintSystem<Bls12>>(
+        self,
+        cs: &mut CS
+    ) -> Result<(), SynthesisError>
+    {
+        let mut value = self.x;
+        let mut x = cs.alloc(|| "x", || value.ok_or(SynthesisError::AssignmentMissing))?;
+
+        for i in 0..self.depth {
+            let mut cs = cs.namespace(|| format!("square {}", i));
+            let squared = value.map(|mut v| { v.square(); v });
+            let y = if i + 1 == self.depth {
+                cs.alloc_input(|| "y", || squared.ok_or(SynthesisError::AssignmentMissing))?
+            } else {
+                cs.alloc(|| "y", || squared.ok_or(SynthesisError::AssignmentMissing))?
+            };
+            cs.enforce(|| "y = x^2", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+
+            value = squared;
+            x = y;
+        }
+
+        Ok(())
+    }
+}
+
+fn allocated_by<T, F: FnOnce() -> T>(f: F) -> (T, usize) {
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    let result = f();
+
+    (result, ALLOCATED.load(Ordering::SeqCst) - before)
+}
+
+#[test]
+fn test_proving_session_allocations_bench() {
+    const DEPTH: usize = 1 << 12;
+
+    let rng = &mut XorShiftRng::from_seed([0x3dbe6259, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+    let params = generate_random_parameters::<Bls12, _, _>(Squarings { x: None, depth: DEPTH }, rng).unwrap();
+    let session = ProvingSession::new(params.clone(), Squarings { x: None, depth: DEPTH }).unwrap();
+
+    let x = Fr::rand(rng);
+    let r = Fr::rand(rng);
+    let s = Fr::rand(rng);
+
+    let start = Instant::now();
+    let (expected, plain_bytes) = allocated_by(|| create_proof(Squarings { x: Some(x), depth: DEPTH }, &params, r, s).unwrap());
+    println!("create_proof allocated {} bytes in {:?}", plain_bytes, start.elapsed());
+
+    for _ in 0..2 {
+        let start = Instant::now();
+        let (proof, session_bytes) = allocated_by(|| session.create_proof(Squarings { x: Some(x), depth: DEPTH }, r, s).unwrap());
+        println!("ProvingSession allocated {} bytes in {:?}", session_bytes, start.elapsed());
+
+        assert!(proof == expected);
+        assert!(session_bytes < plain_bytes);
+    }
+}
@@ -0,0 +1,96 @@
+// Circuits compiled by circom, read from the `.r1cs` and `.wtns` files in
+// `tests/fixtures`. `multiplier` has the wires one, out (public output),
+// k (public input), a, b (private inputs) and t, and the constraints
+//
+//     a * b = t
+//     (-t) * (b + 2k) = -out
+//
+// The witness has a = 3, b = 5, k = 7, so t = 15 and out = 285.
+
+use rand::thread_rng;
+
+use bellman_ce::pairing::bls12_381::Bls12;
+use bellman_ce::pairing::bn256::{Bn256, Fr};
+use bellman_ce::pairing::ff::PrimeField;
+
+use bellman_ce::interop::r1cs_file::{R1csCircuit, R1csFileError};
+use bellman_ce::groth16::{
+    create_random_proof,
+    generate_random_parameters,
+    prepare_verifying_key,
+    verify_proof
+};
+
+use std::path::PathBuf;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+}
+
+#[test]
+fn test_r1cs_file_prove_and_verify() {
+    let rng = &mut thread_rng();
+
+    let circuit = R1csCircuit::<Bn256>::from_files(fixture("multiplier.r1cs"), None::<PathBuf>).unwrap();
+    assert_eq!(circuit.num_wires(), 6);
+    assert_eq!(circuit.num_public_inputs(), 2);
+    assert_eq!(circuit.num_constraints(), 2);
+    assert_eq!(circuit.labels(), &[0, 1, 2, 3, 4, 5]);
+    assert!(circuit.public_inputs().is_none());
+
+    let params = generate_random_parameters(circuit, rng).unwrap();
+    let pvk = prepare_verifying_key(&params.vk);
+
+    let circuit = R1csCircuit::<Bn256>::from_files(fixture("multiplier.r1cs"), Some(fixture("multiplier.wtns"))).unwrap();
+    let inputs = circuit.public_inputs().unwrap().to_vec();
+    assert_eq!(inputs, vec![Fr::from_str("285").unwrap(), Fr::from_str("7").unwrap()]);
+
+    let proof = create_random_proof(circuit, &params, rng).unwrap();
+
+    assert!(verify_proof(&pvk, &proof, &inputs).unwrap());
+    assert!(!verify_proof(&pvk, &proof, &[Fr::from_str("286").unwrap(), Fr::from_str("7").unwrap()]).unwrap());
+}
+
+#[test]
+fn test_r1cs_file_rejects_a_different_field() {
+    match R1csCircuit::<Bls12>::from_files(fixture("multiplier.r1cs"), None::<PathBuf>) {
+        Err(R1csFileError::FieldMismatch) => {},
+        _ => panic!("expected a field mismatch")
+    }
+}
+
+#[test]
+fn test_r1cs_file_rejects_malformed_files() {
+    let r1cs = std::fs::read(fixture("multiplier.r1cs")).unwrap();
+    let wtns = std::fs::read(fixture("multiplier.wtns")).unwrap();
+
+    // the witness is not a constraint file
+    match R1csCircuit::<Bn256>::read(&wtns[..]) {
+        Err(R1csFileError::InvalidFormat(_)) => {},
+        _ => panic!("expected an invalid format")
+    }
+
+    match R1csCircuit::<Bn256>::read(&r1cs[..(r1cs.len() - 1)]) {
+        Err(R1csFileError::InvalidFormat(_)) => {},
+        _ => panic!("expected a truncated section")
+    }
+
+    // a number of constraints the section cannot hold
+    let mut huge = r1cs.clone();
+    huge[84..88].copy_from_slice(&u32::MAX.to_le_bytes());
+    match R1csCircuit::<Bn256>::read(&huge[..]) {
+        Err(R1csFileError::IoError(_)) => {},
+        _ => panic!("expected the constraint section to end")
+    }
+
+    // only the first five wires of the witness
+    let mut circuit = R1csCircuit::<Bn256>::read(&r1cs[..]).unwrap();
+    let mut short = wtns.clone();
+    // the number of values is followed by the type and the size of the values section
+    let count = short.len() - 6 * 32 - 12 - 4;
+    short[count..(count + 4)].copy_from_slice(&5u32.to_le_bytes());
+    match circuit.read_witness(&short[..]) {
+        Err(R1csFileError::WitnessLengthMismatch { expected: 6, got: 5 }) => {},
+        _ => panic!("expected a witness length mismatch")
+    }
+}